@@ -1,5 +1,15 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Burn, CloseAccount, Mint, Token, TokenAccount, Transfer};
+
+#[cfg(not(target_os = "solana"))]
+#[path = "evolution_client.rs"]
+pub mod client;
+mod decay;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -7,41 +17,297 @@ declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 pub mod agent_evolution {
     use super::*;
 
+    /// Creates the program config. Only the program's upgrade authority can
+    /// call this.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        admin: Pubkey,
+        treasury: Pubkey,
+        mint_prices: [u64; 4],
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = admin;
+        config.dmt_mint = ctx.accounts.dmt_mint.key();
+        config.treasury = treasury;
+        config.mint_prices = mint_prices;
+        config.stake_boost_curve = StakeBoostCurve::Linear;
+        config.stake_boost_unit = 1_000_000; // 1 DMT = 1 performance point
+        config.max_stake_boost = MAX_PERFORMANCE;
+        config.training_base_cost = 1_000_000; // 1 DMT
+        config.training_cooldown = 24 * 60 * 60;
+        config.training_fee_policy = FeePolicy {
+            burn_bps: 0,
+            treasury_bps: 10_000,
+            referrer_bps: 0,
+        };
+        config.marketplace_fee_bps = 2_50;
+        config.royalty_bps = 5_00;
+        config.current_season = 0;
+        config.season_ends_at = 0;
+        config.attestors = [Pubkey::default(); MAX_ATTESTORS];
+        config.attestor_count = 0;
+        config.bump = ctx.bumps.config;
+        
+        Ok(())
+    }
+
+    /// Sets the DMT price of minting an agent of each rarity, indexed by
+    /// `Rarity`.
+    pub fn set_mint_prices(ctx: Context<UpdateConfig>, mint_prices: [u64; 4]) -> Result<()> {
+        ctx.accounts.config.mint_prices = mint_prices;
+        
+        Ok(())
+    }
+
+    /// Sets the DMT cost of training a skill from level 0 and the minimum
+    /// time between training sessions. Each level doubles the cost.
+    pub fn set_training_params(
+        ctx: Context<UpdateConfig>,
+        base_cost: u64,
+        cooldown: i64,
+    ) -> Result<()> {
+        require!(cooldown >= 0, AgentError::InvalidCooldown);
+
+        let config = &mut ctx.accounts.config;
+        config.training_base_cost = base_cost;
+        config.training_cooldown = cooldown;
+        
+        Ok(())
+    }
+
+    /// Sets how training fees are split between burning, the treasury and
+    /// the referrer. The shares must sum to 10000 bps.
+    pub fn set_fee_policy(ctx: Context<UpdateConfig>, policy: FeePolicy) -> Result<()> {
+        require!(policy.is_valid(), AgentError::InvalidFeePolicy);
+        ctx.accounts.config.training_fee_policy = policy;
+        
+        Ok(())
+    }
+
+    /// Sets the marketplace fee paid to the treasury and the royalty paid to
+    /// an agent's creator on each sale, in bps of the sale price.
+    pub fn set_marketplace_fees(
+        ctx: Context<UpdateConfig>,
+        marketplace_fee_bps: u16,
+        royalty_bps: u16,
+    ) -> Result<()> {
+        require!(
+            marketplace_fee_bps as u32 + royalty_bps as u32 <= 10_000,
+            AgentError::InvalidFeePolicy
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.marketplace_fee_bps = marketplace_fee_bps;
+        config.royalty_bps = royalty_bps;
+        
+        Ok(())
+    }
+
+    /// Registers a key whose signed attestations `complete_task` accepts.
+    pub fn add_attestor(ctx: Context<UpdateConfig>, attestor: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(!config.is_attestor(&attestor), AgentError::AttestorAlreadyRegistered);
+        require!(
+            (config.attestor_count as usize) < MAX_ATTESTORS,
+            AgentError::TooManyAttestors
+        );
+        
+        let index = config.attestor_count as usize;
+        config.attestors[index] = attestor;
+        config.attestor_count += 1;
+        
+        Ok(())
+    }
+
+    pub fn remove_attestor(ctx: Context<UpdateConfig>, attestor: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let count = config.attestor_count as usize;
+        let index = config.attestors[..count]
+            .iter()
+            .position(|key| *key == attestor)
+            .ok_or(AgentError::UnknownAttestor)?;
+        
+        // Keep the registered keys contiguous
+        config.attestors[index] = config.attestors[count - 1];
+        config.attestors[count - 1] = Pubkey::default();
+        config.attestor_count -= 1;
+        
+        Ok(())
+    }
+
+    /// Sets how staked DMT translates into an agent's stake boost. Existing
+    /// agents pick up the new curve on their next stake or unstake.
+    pub fn set_stake_boost_curve(
+        ctx: Context<UpdateConfig>,
+        curve: StakeBoostCurve,
+        unit: u64,
+        max_boost: u32,
+    ) -> Result<()> {
+        require!(unit > 0, AgentError::InvalidStakeBoostCurve);
+
+        let config = &mut ctx.accounts.config;
+        config.stake_boost_curve = curve;
+        config.stake_boost_unit = unit;
+        config.max_stake_boost = max_boost.min(MAX_PERFORMANCE);
+        
+        Ok(())
+    }
+
     pub fn initialize_agent(
         ctx: Context<InitializeAgent>,
         agent_name: String,
         agent_type: AgentType,
-        initial_level: u8,
+        rarity: Rarity,
+        backend: ModelBackend,
     ) -> Result<()> {
+        require!(agent_type.supports(rarity, backend), AgentError::InvalidRarity);
+
+        // Pay the mint price for this rarity
+        let price = ctx.accounts.config.mint_price(rarity);
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user_token_account.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        );
+        token::transfer(transfer_ctx, price)?;
+
         let agent = &mut ctx.accounts.agent;
         agent.owner = ctx.accounts.owner.key();
+        agent.creator = ctx.accounts.owner.key();
+        agent.bump = ctx.bumps.agent;
+        
+        // Index the agent under its creator's profile
+        let profile = &mut ctx.accounts.profile;
+        profile.owner = ctx.accounts.owner.key();
+        profile.bump = ctx.bumps.profile;
+        agent.index = profile.agent_count;
+        profile.agent_count += 1;
+        
+        // and in its owner's index of held agents
+        let owned_slot = &mut ctx.accounts.owned_slot;
+        owned_slot.agent = agent.key();
+        owned_slot.bump = ctx.bumps.owned_slot;
+        agent.owner_slot = profile.owned_slot_count;
+        profile.owned_slot_count += 1;
         agent.name = agent_name;
         agent.agent_type = agent_type;
-        agent.level = initial_level;
+        agent.rarity = rarity;
+        agent.backend = backend;
+        // Levels are only earned through XP
+        agent.level = 1;
         agent.xp = 0;
-        agent.performance = 100;
+        agent.prestige_rank = 0;
+        agent.season = 0;
+        agent.season_xp = 0;
+        agent.streak_days = 0;
+        agent.last_task_day = 0;
+        agent.performance = PERFORMANCE_FLOOR;
+        agent.stake_boost = 0;
         agent.tasks_completed = 0;
+        agent.stake_position_count = 0;
+        agent.active_stake_positions = 0;
+        agent.total_staked = 0;
+        agent.renter = Pubkey::default();
+        agent.rental_expires_at = 0;
+        agent.skill_levels = [0; Skill::COUNT];
+        agent.last_trained_at = 0;
+        agent.stage = EvolutionStage::Seed;
         agent.created_at = Clock::get()?.unix_timestamp;
         agent.last_updated = Clock::get()?.unix_timestamp;
+
+        emit!(AgentMinted {
+            agent: agent.key(),
+            owner: agent.owner,
+            rarity,
+            backend,
+            price,
+        });
         
         Ok(())
     }
 
-    pub fn complete_task(
-        ctx: Context<CompleteTask>,
+    /// Records a completed task. The XP, boost and skill must be attested
+    /// by a registered attestor through an Ed25519 program instruction
+    /// placed immediately before this one, and each nonce can be used once
+    /// per agent.
+    pub fn complete_task<'info>(
+        ctx: Context<'_, '_, '_, 'info, CompleteTask<'info>>,
+        task_id: u64,
         task_xp: u32,
         performance_boost: u32,
+        skill: Skill,
+        nonce: u64,
     ) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        let attestation = TaskAttestation {
+            agent: ctx.accounts.agent.key(),
+            task_id,
+            task_xp,
+            performance_boost,
+            skill,
+            nonce,
+        };
+        let attestor = verify_attestation(
+            &ctx.accounts.instructions,
+            &ctx.accounts.config,
+            &attestation.try_to_vec()?,
+        )?;
+        
+        let nonce_record = &mut ctx.accounts.nonce_record;
+        nonce_record.agent = attestation.agent;
+        nonce_record.nonce = nonce;
+        nonce_record.attestor = attestor;
+        nonce_record.used_at = current_time;
+        nonce_record.bump = ctx.bumps.nonce_record;
+        
         let agent = &mut ctx.accounts.agent;
+        require!(
+            agent.is_operator(ctx.accounts.operator.key(), current_time),
+            AgentError::Unauthorized
+        );
+        agent.touch(current_time);
+        
+        // Add XP scaled by rarity, prestige and the agent's level in the
+        // task's skill. The caller picks `skill`, so the skill multiplier is
+        // only as trustworthy as the attestation above, which signs it
+        // together with the XP.
+        let skill_bps = skill_xp_multiplier_bps(agent.skill_level(skill));
+        let earned_xp = task_xp as u64 * agent.rarity.xp_multiplier_bps() / 10_000
+            * prestige_xp_multiplier_bps(agent.prestige_rank) / 10_000
+            * skill_bps / 10_000;
+        agent.xp = agent.xp.checked_add(earned_xp).ok_or(AgentError::XpOverflow)?;
         
-        // Add XP and update performance
-        agent.xp += task_xp;
-        agent.performance = agent.performance.saturating_add(performance_boost);
+        // Count the XP toward the season in progress and rank the agent on
+        // its leaderboard, which must be passed while a season is running
+        let config = &ctx.accounts.config;
+        if current_time < config.season_ends_at {
+            if agent.season != config.current_season {
+                agent.season = config.current_season;
+                agent.season_xp = 0;
+            }
+            agent.season_xp = agent
+                .season_xp
+                .checked_add(earned_xp)
+                .ok_or(AgentError::XpOverflow)?;
+            
+            let leaderboard = ctx
+                .accounts
+                .leaderboard
+                .as_mut()
+                .ok_or(AgentError::LeaderboardRequired)?;
+            require!(leaderboard.is_active(current_time), AgentError::SeasonNotActive);
+            leaderboard.submit(agent.key(), agent.season_xp, agent.effective_performance());
+        }
+        agent.boost_performance(performance_boost);
         agent.tasks_completed += 1;
-        agent.last_updated = Clock::get()?.unix_timestamp;
+        agent.record_task_day(current_time);
         
         // Check for level up
-        let new_level = calculate_level(agent.xp);
+        let new_level = calculate_level(agent.xp).min(agent.rarity.level_cap());
         if new_level > agent.level {
             agent.level = new_level;
             emit!(AgentLevelUp {
@@ -51,326 +317,3311 @@ pub mod agent_evolution {
             });
         }
         
+        emit!(TaskAttested {
+            agent: agent.key(),
+            attestor,
+            task_id,
+            nonce,
+            earned_xp,
+        });
+        
+        if let Some(registry) = &ctx.accounts.achievement_registry {
+            award_badges(
+                &ctx.accounts.agent,
+                registry,
+                ctx.remaining_accounts,
+                &ctx.accounts.operator,
+                &ctx.accounts.system_program,
+                current_time,
+            )?;
+        }
+        
         Ok(())
     }
 
-    pub fn train_agent(
-        ctx: Context<TrainAgent>,
-        training_cost: u64,
-        skill_boost: u32,
+    /// Trains one skill up a level. The cost doubles with each level, the
+    /// skill's prerequisite must already be at the target level, and
+    /// sessions are limited by the configured cooldown. While the agent is
+    /// rented only the renter can train it.
+    pub fn train_agent<'info>(
+        ctx: Context<'_, '_, '_, 'info, TrainAgent<'info>>,
+        skill: Skill,
     ) -> Result<()> {
+        let config = &ctx.accounts.config;
         let agent = &mut ctx.accounts.agent;
+        let current_time = Clock::get()?.unix_timestamp;
         
-        // Transfer training cost
-        let transfer_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.user_token_account.to_account_info(),
-                to: ctx.accounts.training_fee_account.to_account_info(),
-                authority: ctx.accounts.owner.to_account_info(),
-            },
+        require!(
+            agent.is_operator(ctx.accounts.operator.key(), current_time),
+            AgentError::Unauthorized
+        );
+        require!(
+            agent.last_trained_at == 0
+                || current_time >= agent.last_trained_at + config.training_cooldown,
+            AgentError::TrainingCooldown
         );
-        token::transfer(transfer_ctx, training_cost)?;
         
-        // Apply skill boost
-        agent.performance = agent.performance.saturating_add(skill_boost);
-        agent.last_updated = Clock::get()?.unix_timestamp;
+        let current_level = agent.skill_level(skill);
+        require!(current_level < MAX_SKILL_LEVEL, AgentError::SkillMaxed);
+        let new_level = current_level + 1;
+        if let Some(prerequisite) = skill.prerequisite() {
+            require!(
+                agent.skill_level(prerequisite) >= new_level,
+                AgentError::SkillPrerequisiteNotMet
+            );
+        }
+        
+        let training_cost = config.training_base_cost.saturating_mul(1 << current_level);
+        agent.touch(current_time);
+        
+        // Split the training cost per the fee policy. Without a referrer
+        // their share goes to the treasury.
+        let policy = config.training_fee_policy;
+        let burned = (training_cost as u128 * policy.burn_bps as u128 / 10_000) as u64;
+        let to_referrer = match ctx.accounts.referrer_token_account {
+            Some(_) => (training_cost as u128 * policy.referrer_bps as u128 / 10_000) as u64,
+            None => 0,
+        };
+        let to_treasury = training_cost - burned - to_referrer;
+        
+        if burned > 0 {
+            let burn_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.dmt_mint.to_account_info(),
+                    from: ctx.accounts.user_token_account.to_account_info(),
+                    authority: ctx.accounts.operator.to_account_info(),
+                },
+            );
+            token::burn(burn_ctx, burned)?;
+        }
+        
+        if to_treasury > 0 {
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token_account.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.operator.to_account_info(),
+                },
+            );
+            token::transfer(transfer_ctx, to_treasury)?;
+        }
+        
+        let referrer = ctx.accounts.referrer_token_account.as_ref().map(|account| account.key());
+        if let Some(referrer_token_account) = &ctx.accounts.referrer_token_account {
+            if to_referrer > 0 {
+                let transfer_ctx = CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.user_token_account.to_account_info(),
+                        to: referrer_token_account.to_account_info(),
+                        authority: ctx.accounts.operator.to_account_info(),
+                    },
+                );
+                token::transfer(transfer_ctx, to_referrer)?;
+            }
+        }
+        
+        agent.skill_levels[skill as usize] = new_level;
+        agent.last_trained_at = current_time;
+        agent.boost_performance(new_level as u32 * TRAINING_BOOST_PER_LEVEL);
         
         emit!(AgentTrained {
             agent: agent.key(),
+            skill,
+            new_skill_level: new_level,
             training_cost,
-            new_performance: agent.performance,
+            burned,
+            to_treasury,
+            to_referrer,
+            referrer,
+            new_performance: agent.effective_performance(),
+        });
+        
+        if let Some(registry) = &ctx.accounts.achievement_registry {
+            award_badges(
+                &ctx.accounts.agent,
+                registry,
+                ctx.remaining_accounts,
+                &ctx.accounts.operator,
+                &ctx.accounts.system_program,
+                current_time,
+            )?;
+        }
+        
+        Ok(())
+    }
+
+    /// Moves an agent to its next evolution stage once it reaches the stage's
+    /// level threshold, and points its metadata at the new avatar.
+    pub fn evolve(ctx: Context<Evolve>, uri: String) -> Result<()> {
+        require!(uri.len() <= MAX_METADATA_URI_LEN, AgentError::MetadataUriTooLong);
+
+        let agent = &mut ctx.accounts.agent;
+        let next_stage = agent.stage.next().ok_or(AgentError::MaxStageReached)?;
+        require!(
+            agent.level >= next_stage.required_level(),
+            AgentError::EvolutionLocked
+        );
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        agent.touch(current_time);
+        agent.stage = next_stage;
+        
+        // Refresh the metadata account read by the avatar renderer
+        let metadata = &mut ctx.accounts.metadata;
+        metadata.agent = agent.key();
+        metadata.stage = next_stage;
+        metadata.uri = uri.clone();
+        metadata.level = agent.level;
+        metadata.rarity = agent.rarity;
+        metadata.backend = agent.backend;
+        metadata.skill_levels = agent.skill_levels;
+        metadata.prestige_rank = agent.prestige_rank;
+        metadata.updated_at = current_time;
+        metadata.bump = ctx.bumps.metadata;
+        
+        emit!(AgentEvolved {
+            agent: agent.key(),
+            metadata: metadata.key(),
+            stage: next_stage,
+            level: agent.level,
+            uri,
+        });
+        
+        Ok(())
+    }
+
+    /// Merges `secondary` into `primary` and closes `secondary`. The fused
+    /// agent keeps the combined XP less the fusion tax, the rarer agent's
+    /// rarity and backend, and the average of both agents' skills. Neither
+    /// agent may have stake outstanding, and `secondary` may not have an
+    /// open rental offer.
+    pub fn fuse_agents(ctx: Context<FuseAgents>) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        let secondary = &mut ctx.accounts.secondary;
+        let primary = &mut ctx.accounts.primary;
+        
+        require!(
+            primary.active_stake_positions == 0 && secondary.active_stake_positions == 0,
+            AgentError::AgentHasStake
+        );
+        require!(
+            primary.agent_type == secondary.agent_type,
+            AgentError::IncompatibleAgents
+        );
+        require!(
+            !primary.is_rented(current_time) && !secondary.is_rented(current_time),
+            AgentError::AgentRented
+        );
+        
+        primary.touch(current_time);
+        secondary.touch(current_time);
+        
+        // Combine XP less the fusion tax
+        let combined_xp = primary
+            .xp
+            .checked_add(secondary.xp)
+            .ok_or(AgentError::XpOverflow)?;
+        let fusion_tax = (combined_xp as u128 * FUSION_TAX_BPS as u128 / 10_000) as u64;
+        primary.xp = combined_xp - fusion_tax;
+        
+        // The rarer agent's rarity and backend win
+        if secondary.rarity > primary.rarity {
+            primary.rarity = secondary.rarity;
+            primary.backend = secondary.backend;
+        }
+        
+        for (level, other) in primary.skill_levels.iter_mut().zip(secondary.skill_levels) {
+            *level = ((*level as u16 + other as u16) / 2) as u8;
+        }
+        
+        // The level follows the taxed XP, never either agent's old level
+        primary.level = calculate_level(primary.xp).min(primary.rarity.level_cap());
+        primary.performance = primary.performance.max(secondary.performance);
+        primary.stage = primary.stage.max(secondary.stage);
+        primary.prestige_rank = primary.prestige_rank.max(secondary.prestige_rank);
+        primary.tasks_completed = primary.tasks_completed.saturating_add(secondary.tasks_completed);
+        
+        // The stage and its URI are only changed by `evolve`
+        if let Some(metadata) = &mut ctx.accounts.metadata {
+            metadata.level = primary.level;
+            metadata.rarity = primary.rarity;
+            metadata.backend = primary.backend;
+            metadata.skill_levels = primary.skill_levels;
+            metadata.prestige_rank = primary.prestige_rank;
+            metadata.updated_at = current_time;
+        }
+        
+        emit!(AgentsFused {
+            owner: primary.owner,
+            primary: primary.key(),
+            secondary: secondary.key(),
+            xp: primary.xp,
+            fusion_tax,
+            rarity: primary.rarity,
+            level: primary.level,
+        });
+        
+        Ok(())
+    }
+
+    /// Resets an agent at its rarity's level cap to level 1 in exchange for
+    /// a permanent prestige rank, which raises its XP multiplier and badge.
+    pub fn prestige(ctx: Context<Prestige>) -> Result<()> {
+        let agent = &mut ctx.accounts.agent;
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        // Gate on XP rather than the stored level, which fusion could inflate
+        require!(
+            calculate_level(agent.xp) >= agent.rarity.level_cap(),
+            AgentError::PrestigeLocked
+        );
+        require!(
+            agent.prestige_rank < MAX_PRESTIGE_RANK,
+            AgentError::MaxPrestigeReached
+        );
+        require!(!agent.is_rented(current_time), AgentError::AgentRented);
+        
+        agent.touch(current_time);
+        agent.level = 1;
+        agent.xp = 0;
+        agent.prestige_rank += 1;
+        
+        if let Some(metadata) = &mut ctx.accounts.metadata {
+            metadata.level = agent.level;
+            metadata.prestige_rank = agent.prestige_rank;
+            metadata.updated_at = current_time;
+        }
+        
+        emit!(AgentPrestiged {
+            agent: agent.key(),
+            prestige_rank: agent.prestige_rank,
+            badge: agent.prestige_badge(),
+        });
+        
+        Ok(())
+    }
+
+    /// Creates the staking pool and its two PDA-owned vaults. Rewards are
+    /// paid from `reward_vault`, which is funded separately from the
+    /// stakers' principal in `stake_vault`.
+    pub fn initialize_staking_pool(
+        ctx: Context<InitializeStakingPool>,
+        emission_rate: u64,
+        early_unstake_penalty_bps: u16,
+        penalty_destination: PenaltyDestination,
+    ) -> Result<()> {
+        require!(early_unstake_penalty_bps <= 10_000, AgentError::InvalidPenalty);
+
+        let pool = &mut ctx.accounts.staking_pool;
+        pool.dmt_mint = ctx.accounts.config.dmt_mint;
+        pool.stake_vault = ctx.accounts.stake_vault.key();
+        pool.reward_vault = ctx.accounts.reward_vault.key();
+        pool.emission_rate = emission_rate;
+        pool.acc_reward_per_share = 0;
+        pool.last_update_at = Clock::get()?.unix_timestamp;
+        pool.total_staked = 0;
+        pool.unallocated_rewards = 0;
+        pool.early_unstake_penalty_bps = early_unstake_penalty_bps;
+        pool.penalty_destination = penalty_destination;
+        pool.bump = ctx.bumps.staking_pool;
+        
+        Ok(())
+    }
+
+    /// Sets the reward tokens emitted per second across all stakers.
+    pub fn set_emission_rate(ctx: Context<UpdateStakingPool>, emission_rate: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.staking_pool;
+        // Settle emissions at the old rate first
+        pool.update(Clock::get()?.unix_timestamp);
+        pool.emission_rate = emission_rate;
+        
+        emit!(EmissionRateUpdated {
+            staking_pool: pool.key(),
+            emission_rate,
+        });
+        
+        Ok(())
+    }
+
+    /// Sets the penalty charged on stake withdrawn at the start of its lock
+    /// and where penalties go. The charge falls linearly to zero at lock
+    /// expiry.
+    pub fn set_penalty_policy(
+        ctx: Context<UpdateStakingPool>,
+        early_unstake_penalty_bps: u16,
+        penalty_destination: PenaltyDestination,
+    ) -> Result<()> {
+        require!(early_unstake_penalty_bps <= 10_000, AgentError::InvalidPenalty);
+
+        let pool = &mut ctx.accounts.staking_pool;
+        pool.early_unstake_penalty_bps = early_unstake_penalty_bps;
+        pool.penalty_destination = penalty_destination;
+        
+        emit!(PenaltyPolicyUpdated {
+            staking_pool: pool.key(),
+            early_unstake_penalty_bps,
+            penalty_destination,
+        });
+        
+        Ok(())
+    }
+
+    /// Adds reward tokens to the vault. Emissions only ever pay out funded
+    /// tokens, and stop once the funded balance is used up.
+    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64) -> Result<()> {
+        // Settle emissions up to now first so the new funds only back
+        // emissions from here on
+        let pool = &mut ctx.accounts.staking_pool;
+        pool.update(Clock::get()?.unix_timestamp);
+        pool.unallocated_rewards += amount;
+        
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.funder_token_account.to_account_info(),
+                to: ctx.accounts.reward_vault.to_account_info(),
+                authority: ctx.accounts.funder.to_account_info(),
+            },
+        );
+        token::transfer(transfer_ctx, amount)?;
+        
+        emit!(RewardsFunded {
+            staking_pool: ctx.accounts.staking_pool.key(),
+            funder: ctx.accounts.funder.key(),
+            amount,
         });
         
         Ok(())
     }
 
-    pub fn stake_agent(
-        ctx: Context<StakeAgent>,
+    pub fn stake_agent<'info>(
+        ctx: Context<'_, '_, '_, 'info, StakeAgent<'info>>,
         stake_amount: u64,
         lock_period: i64,
     ) -> Result<()> {
+        require!(stake_amount > 0, AgentError::InvalidStakeAmount);
+        require!(lock_period >= 0, AgentError::InvalidLockPeriod);
+
         let agent = &mut ctx.accounts.agent;
-        let staking_info = &mut ctx.accounts.staking_info;
+        let pool = &mut ctx.accounts.staking_pool;
+        let position = &mut ctx.accounts.stake_position;
+        let current_time = Clock::get()?.unix_timestamp;
+        agent.touch(current_time);
+        pool.update(current_time);
         
         // Transfer stake amount
         let transfer_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
                 from: ctx.accounts.user_token_account.to_account_info(),
-                to: ctx.accounts.staking_pool.to_account_info(),
+                to: ctx.accounts.stake_vault.to_account_info(),
                 authority: ctx.accounts.owner.to_account_info(),
             },
         );
         token::transfer(transfer_ctx, stake_amount)?;
         
-        // Update staking info
-        staking_info.agent = agent.key();
-        staking_info.owner = ctx.accounts.owner.key();
-        staking_info.stake_amount = stake_amount;
-        staking_info.lock_period = lock_period;
-        staking_info.staked_at = Clock::get()?.unix_timestamp;
-        staking_info.is_active = true;
+        // Open a new position
+        position.agent = agent.key();
+        position.owner = ctx.accounts.owner.key();
+        position.index = agent.stake_position_count;
+        position.amount = stake_amount;
+        position.lock_period = lock_period;
+        position.staked_at = current_time;
+        position.reward_debt = pool.accrued(stake_amount);
+        position.pending_rewards = 0;
+        position.bump = ctx.bumps.stake_position;
         
-        // Boost agent performance based on stake
-        let performance_boost = (stake_amount / 1_000_000) as u32; // 1 DMT = 1 performance point
-        agent.performance = agent.performance.saturating_add(performance_boost);
+        pool.total_staked += stake_amount;
+        agent.stake_position_count += 1;
+        agent.active_stake_positions += 1;
+        agent.total_staked += stake_amount;
+        
+        // Recalculate the boost from the agent's current stake
+        agent.stake_boost = ctx.accounts.config.stake_boost(agent.total_staked);
         
         emit!(AgentStaked {
             agent: agent.key(),
+            position: position.key(),
             stake_amount,
             lock_period,
-            new_performance: agent.performance,
+            total_staked: agent.total_staked,
+            new_performance: agent.effective_performance(),
         });
         
+        if let Some(registry) = &ctx.accounts.achievement_registry {
+            award_badges(
+                &ctx.accounts.agent,
+                registry,
+                ctx.remaining_accounts,
+                &ctx.accounts.owner,
+                &ctx.accounts.system_program,
+                current_time,
+            )?;
+        }
+        
         Ok(())
     }
 
+    /// Withdraws a stake position's principal and pays its rewards. The
+    /// position is closed once nothing is owed; rewards the vault can't
+    /// cover yet stay claimable.
     pub fn unstake_agent(ctx: Context<UnstakeAgent>) -> Result<()> {
-        let staking_info = &mut ctx.accounts.staking_info;
-        let agent = &mut ctx.accounts.agent;
-        
-        require!(staking_info.is_active, AgentError::NotStaked);
+        let position = &ctx.accounts.stake_position;
         require!(
-            Clock::get()?.unix_timestamp >= staking_info.staked_at + staking_info.lock_period,
+            Clock::get()?.unix_timestamp >= position.staked_at + position.lock_period,
             AgentError::LockPeriodNotExpired
         );
         
-        // Calculate rewards
-        let time_staked = Clock::get()?.unix_timestamp - staking_info.staked_at;
-        let apy = 12_50; // 12.5% APY (in basis points)
-        let rewards = (staking_info.stake_amount as u128)
-            .checked_mul(apy as u128)
-            .unwrap()
-            .checked_mul(time_staked as u128)
-            .unwrap()
-            .checked_div(365 * 24 * 60 * 60 * 10000) // Convert to daily rate
-            .unwrap() as u64;
+        let amount = position.amount;
+        withdraw_stake(ctx, amount)
+    }
+
+    /// Withdraws part of a stake position. Before the lock expires the
+    /// withdrawn amount is charged the pool's early unstake penalty.
+    pub fn partial_unstake(ctx: Context<UnstakeAgent>, amount: u64) -> Result<()> {
+        require!(
+            amount > 0 && amount <= ctx.accounts.stake_position.amount,
+            AgentError::InvalidStakeAmount
+        );
+        
+        withdraw_stake(ctx, amount)
+    }
+
+    /// Withdraws a whole stake position before its lock expires, paying the
+    /// early unstake penalty.
+    pub fn early_unstake(ctx: Context<UnstakeAgent>) -> Result<()> {
+        let amount = ctx.accounts.stake_position.amount;
+        withdraw_stake(ctx, amount)
+    }
+
+    /// Pays a position's accrued rewards without unstaking.
+    pub fn claim_staking_rewards(ctx: Context<ClaimStakingRewards>) -> Result<()> {
+        let pool = &mut ctx.accounts.staking_pool;
+        let position = &mut ctx.accounts.stake_position;
+        
+        pool.update(Clock::get()?.unix_timestamp);
+        position.settle(pool);
+        
+        let bump = [pool.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"staking_pool", &bump]];
+        let rewards = pay_rewards(
+            position,
+            &ctx.accounts.reward_vault,
+            ctx.accounts.user_token_account.to_account_info(),
+            pool.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            signer_seeds,
+        )?;
+        
+        emit!(StakingRewardsClaimed {
+            agent: position.agent,
+            position: position.key(),
+            amount: rewards,
+        });
+        
+        // Positions left open by an underfunded unstake close once paid out
+        if position.amount == 0 && position.pending_rewards == 0 {
+            ctx.accounts.stake_position.close(ctx.accounts.owner.to_account_info())?;
+        }
+        
+        Ok(())
+    }
+
+    /// Lists an agent for sale. The listing PDA holds the agent in escrow
+    /// as its owner until it is bought or delisted. Any rental offer must be
+    /// cancelled first so it can't outlive the sale.
+    pub fn list_agent(ctx: Context<ListAgent>, price: u64) -> Result<()> {
+        require!(price > 0, AgentError::InvalidPrice);
+
+        let agent = &mut ctx.accounts.agent;
+        require!(agent.active_stake_positions == 0, AgentError::AgentHasStake);
+        require!(
+            !agent.is_rented(Clock::get()?.unix_timestamp),
+            AgentError::AgentRented
+        );
+        
+        let listing = &mut ctx.accounts.listing;
+        listing.agent = agent.key();
+        listing.seller = ctx.accounts.owner.key();
+        listing.price = price;
+        listing.created_at = Clock::get()?.unix_timestamp;
+        listing.bump = ctx.bumps.listing;
+        
+        agent.owner = listing.key();
+        
+        emit!(AgentListed {
+            agent: agent.key(),
+            listing: listing.key(),
+            seller: listing.seller,
+            price,
+        });
+        
+        Ok(())
+    }
+
+    /// Buys a listed agent at `expected_price`, which guards against the
+    /// seller repricing the listing before the purchase lands. The buyer
+    /// pays into the listing's escrow vault, which then splits the price
+    /// between the marketplace fee, the creator's royalty and the seller,
+    /// and the agent moves to the buyer in the same instruction.
+    pub fn buy_agent(ctx: Context<BuyAgent>, expected_price: u64) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let listing = &ctx.accounts.listing;
+        let price = listing.price;
+        require!(price == expected_price, AgentError::PriceMismatch);
         
-        // Transfer stake back + rewards
-        let total_return = staking_info.stake_amount + rewards;
         let transfer_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
-                from: ctx.accounts.staking_pool.to_account_info(),
-                to: ctx.accounts.user_token_account.to_account_info(),
-                authority: ctx.accounts.staking_authority.to_account_info(),
+                from: ctx.accounts.buyer_token_account.to_account_info(),
+                to: ctx.accounts.escrow_vault.to_account_info(),
+                authority: ctx.accounts.buyer.to_account_info(),
+            },
+        );
+        token::transfer(transfer_ctx, price)?;
+        
+        let marketplace_fee = (price as u128 * config.marketplace_fee_bps as u128 / 10_000) as u64;
+        let royalty = (price as u128 * config.royalty_bps as u128 / 10_000) as u64;
+        let seller_proceeds = price - marketplace_fee - royalty;
+        
+        let bump = [listing.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"listing", listing.agent.as_ref(), &bump]];
+        for (to, amount) in [
+            (ctx.accounts.treasury.to_account_info(), marketplace_fee),
+            (ctx.accounts.creator_token_account.to_account_info(), royalty),
+            (ctx.accounts.seller_token_account.to_account_info(), seller_proceeds),
+        ] {
+            if amount > 0 {
+                let transfer_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.escrow_vault.to_account_info(),
+                        to,
+                        authority: listing.to_account_info(),
+                    },
+                    signer_seeds,
+                );
+                token::transfer(transfer_ctx, amount)?;
+            }
+        }
+        
+        // The escrow is emptied above; return its rent to the buyer
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.escrow_vault.to_account_info(),
+                destination: ctx.accounts.buyer.to_account_info(),
+                authority: listing.to_account_info(),
             },
+            signer_seeds,
         );
-        token::transfer(transfer_ctx, total_return)?;
+        token::close_account(close_ctx)?;
+        
+        let agent = &mut ctx.accounts.agent;
+        agent.owner = ctx.accounts.buyer.key();
         
-        // Reset staking info
-        staking_info.is_active = false;
-        staking_info.stake_amount = 0;
+        // Move the agent from the seller's index of held agents to the
+        // buyer's; the seller's slot is closed on exit
+        let buyer_profile = &mut ctx.accounts.buyer_profile;
+        buyer_profile.owner = agent.owner;
+        buyer_profile.bump = ctx.bumps.buyer_profile;
+        let buyer_slot = &mut ctx.accounts.buyer_slot;
+        buyer_slot.agent = agent.key();
+        buyer_slot.bump = ctx.bumps.buyer_slot;
+        agent.owner_slot = buyer_profile.owned_slot_count;
+        buyer_profile.owned_slot_count += 1;
         
-        emit!(AgentUnstaked {
+        emit!(AgentSold {
             agent: agent.key(),
-            stake_returned: staking_info.stake_amount,
-            rewards_earned: rewards,
+            seller: listing.seller,
+            buyer: agent.owner,
+            price,
+            marketplace_fee,
+            royalty,
         });
         
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct InitializeAgent<'info> {
-    #[account(
-        init,
-        payer = owner,
-        space = 8 + Agent::LEN,
-        seeds = [b"agent", owner.key().as_ref()],
-        bump
-    )]
-    pub agent: Account<'info, Agent>,
-    
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Cancels a listing and returns the agent to the seller.
+    pub fn delist_agent(ctx: Context<DelistAgent>) -> Result<()> {
+        let agent = &mut ctx.accounts.agent;
+        agent.owner = ctx.accounts.seller.key();
+        
+        emit!(AgentDelisted {
+            agent: agent.key(),
+            listing: ctx.accounts.listing.key(),
+            seller: agent.owner,
+        });
+        
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct CompleteTask<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", agent.owner.as_ref()],
-        bump,
-        has_one = owner
+    /// Offers an agent for rent at a per-day DMT price, for up to
+    /// `max_days` at a time. Rent is held in the offer's escrow vault.
+    pub fn offer_rental(ctx: Context<OfferRental>, price_per_day: u64, max_days: u16) -> Result<()> {
+        require!(price_per_day > 0, AgentError::InvalidPrice);
+        require!(max_days > 0, AgentError::InvalidRentalDuration);
+
+        let rental = &mut ctx.accounts.rental;
+        rental.agent = ctx.accounts.agent.key();
+        rental.owner = ctx.accounts.owner.key();
+        rental.escrow_vault = ctx.accounts.escrow_vault.key();
+        rental.price_per_day = price_per_day;
+        rental.max_days = max_days;
+        rental.renter = Pubkey::default();
+        rental.starts_at = 0;
+        rental.expires_at = 0;
+        rental.total_paid = 0;
+        rental.claimed = 0;
+        rental.bump = ctx.bumps.rental;
+        
+        emit!(RentalOffered {
+            agent: rental.agent,
+            rental: rental.key(),
+            price_per_day,
+            max_days,
+        });
+        
+        Ok(())
+    }
+
+    /// Rents an agent for `days`, paying the full rent into escrow upfront.
+    /// Until expiry the renter operates the agent in place of the owner.
+    pub fn rent_agent(ctx: Context<RentAgent>, days: u16) -> Result<()> {
+        let rental = &mut ctx.accounts.rental;
+        require!(rental.renter == Pubkey::default(), AgentError::AgentRented);
+        require!(
+            days > 0 && days <= rental.max_days,
+            AgentError::InvalidRentalDuration
+        );
+        
+        let rent = rental
+            .price_per_day
+            .checked_mul(days as u64)
+            .ok_or(AgentError::InvalidPrice)?;
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.renter_token_account.to_account_info(),
+                to: ctx.accounts.escrow_vault.to_account_info(),
+                authority: ctx.accounts.renter.to_account_info(),
+            },
+        );
+        token::transfer(transfer_ctx, rent)?;
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        rental.renter = ctx.accounts.renter.key();
+        rental.starts_at = current_time;
+        rental.expires_at = current_time + days as i64 * SECONDS_PER_DAY;
+        rental.total_paid = rent;
+        rental.claimed = 0;
+        
+        let agent = &mut ctx.accounts.agent;
+        agent.renter = rental.renter;
+        agent.rental_expires_at = rental.expires_at;
+        
+        emit!(AgentRented {
+            agent: agent.key(),
+            renter: rental.renter,
+            days,
+            rent,
+            expires_at: rental.expires_at,
+        });
+        
+        Ok(())
+    }
+
+    /// Pays the owner the rent streamed so far.
+    pub fn claim_rental_payment(ctx: Context<SettleRental>) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        let amount = pay_streamed_rent(
+            &mut ctx.accounts.rental,
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.owner_token_account,
+            &ctx.accounts.token_program,
+            current_time,
+        )?;
+        
+        emit!(RentalPaymentClaimed {
+            agent: ctx.accounts.rental.agent,
+            owner: ctx.accounts.owner.key(),
+            amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Ends an expired rental: pays the owner the rest of the rent and
+    /// returns control of the agent. The offer stays open for new renters.
+    pub fn end_rental(ctx: Context<SettleRental>) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            ctx.accounts.rental.renter != Pubkey::default(),
+            AgentError::NotRented
+        );
+        require!(
+            current_time >= ctx.accounts.rental.expires_at,
+            AgentError::RentalNotExpired
+        );
+        
+        let amount = pay_streamed_rent(
+            &mut ctx.accounts.rental,
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.owner_token_account,
+            &ctx.accounts.token_program,
+            current_time,
+        )?;
+        
+        let rental = &mut ctx.accounts.rental;
+        let renter = rental.renter;
+        rental.renter = Pubkey::default();
+        
+        let agent = &mut ctx.accounts.agent;
+        agent.renter = Pubkey::default();
+        agent.rental_expires_at = 0;
+        
+        emit!(RentalEnded {
+            agent: agent.key(),
+            renter,
+            amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Withdraws an agent's rental offer and closes its escrow vault. The
+    /// agent must not be rented.
+    pub fn cancel_rental_offer(ctx: Context<CancelRentalOffer>) -> Result<()> {
+        require!(
+            ctx.accounts.rental.renter == Pubkey::default(),
+            AgentError::AgentRented
+        );
+        
+        let rental = &ctx.accounts.rental;
+        let bump = [rental.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"rental", rental.agent.as_ref(), &bump]];
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.escrow_vault.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: rental.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::close_account(close_ctx)?;
+        
+        Ok(())
+    }
+
+    /// Opens the next season's leaderboard, running for `duration` seconds.
+    /// The top `SEASON_PRIZE_SLOTS` agents can claim `prizes`, which the
+    /// admin escrows in the season's prize vault upfront.
+    pub fn start_season(
+        ctx: Context<StartSeason>,
+        duration: i64,
+        prizes: [u64; SEASON_PRIZE_SLOTS],
+    ) -> Result<()> {
+        require!(duration > 0, AgentError::InvalidSeason);
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let config = &mut ctx.accounts.config;
+        require!(current_time >= config.season_ends_at, AgentError::SeasonNotEnded);
+        
+        let prize_total = prizes
+            .iter()
+            .try_fold(0u64, |total, prize| total.checked_add(*prize))
+            .ok_or(AgentError::InvalidSeason)?;
+        if prize_total > 0 {
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.admin_token_account.to_account_info(),
+                    to: ctx.accounts.prize_vault.to_account_info(),
+                    authority: ctx.accounts.admin.to_account_info(),
+                },
+            );
+            token::transfer(transfer_ctx, prize_total)?;
+        }
+        
+        config.current_season += 1;
+        config.season_ends_at = current_time + duration;
+        
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        leaderboard.season = config.current_season;
+        leaderboard.starts_at = current_time;
+        leaderboard.ends_at = config.season_ends_at;
+        leaderboard.entries = [LeaderboardEntry::default(); LEADERBOARD_CAPACITY];
+        leaderboard.entry_count = 0;
+        leaderboard.finalized = false;
+        leaderboard.prize_vault = ctx.accounts.prize_vault.key();
+        leaderboard.prizes = prizes;
+        leaderboard.prizes_claimed = 0;
+        leaderboard.bump = ctx.bumps.leaderboard;
+        
+        emit!(SeasonStarted {
+            season: leaderboard.season,
+            ends_at: leaderboard.ends_at,
+            prizes,
+        });
+        
+        Ok(())
+    }
+
+    /// Freezes an ended season's standings as its final snapshot. Anyone
+    /// can call this once the season is over.
+    pub fn finalize_season(ctx: Context<FinalizeSeason>) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        require!(!leaderboard.finalized, AgentError::SeasonFinalized);
+        require!(
+            Clock::get()?.unix_timestamp >= leaderboard.ends_at,
+            AgentError::SeasonNotEnded
+        );
+        
+        leaderboard.finalized = true;
+        
+        emit!(SeasonFinalized {
+            season: leaderboard.season,
+            standings: leaderboard.standings().to_vec(),
+        });
+        
+        Ok(())
+    }
+
+    /// Pays the prize for `rank` (0 is first place) in a finalized season
+    /// to the current owner of the agent that finished there. Prizes can be
+    /// claimed for `PRIZE_CLAIM_WINDOW` after the season ends.
+    pub fn claim_season_prize(ctx: Context<ClaimSeasonPrize>, rank: u8) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        let rank = rank as usize;
+        require!(leaderboard.finalized, AgentError::SeasonNotEnded);
+        require!(
+            Clock::get()?.unix_timestamp < leaderboard.claim_deadline(),
+            AgentError::PrizeClaimClosed
+        );
+        require!(
+            rank < SEASON_PRIZE_SLOTS && rank < leaderboard.entry_count as usize,
+            AgentError::NoPrize
+        );
+        require!(
+            leaderboard.entries[rank].agent == ctx.accounts.agent.key(),
+            AgentError::NoPrize
+        );
+        require!(
+            leaderboard.prizes_claimed & (1 << rank) == 0,
+            AgentError::PrizeAlreadyClaimed
+        );
+        
+        let amount = leaderboard.prizes[rank];
+        if amount > 0 {
+            let season = leaderboard.season.to_le_bytes();
+            let bump = [leaderboard.bump];
+            let signer_seeds: &[&[&[u8]]] = &[&[b"leaderboard", &season, &bump]];
+            let transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.prize_vault.to_account_info(),
+                    to: ctx.accounts.owner_token_account.to_account_info(),
+                    authority: leaderboard.to_account_info(),
+                },
+                signer_seeds,
+            );
+            token::transfer(transfer_ctx, amount)?;
+        }
+        leaderboard.prizes_claimed |= 1 << rank;
+        
+        emit!(SeasonPrizeClaimed {
+            season: leaderboard.season,
+            agent: ctx.accounts.agent.key(),
+            rank: rank as u8,
+            amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Moves a finalized season's unclaimed and unfilled prizes to the
+    /// treasury once its claim window has closed, and closes the prize vault.
+    pub fn sweep_season_prizes(ctx: Context<SweepSeasonPrizes>) -> Result<()> {
+        let leaderboard = &ctx.accounts.leaderboard;
+        require!(leaderboard.finalized, AgentError::SeasonNotEnded);
+        require!(
+            Clock::get()?.unix_timestamp >= leaderboard.claim_deadline(),
+            AgentError::PrizeClaimOpen
+        );
+        
+        let season = leaderboard.season.to_le_bytes();
+        let bump = [leaderboard.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"leaderboard", &season, &bump]];
+        let amount = ctx.accounts.prize_vault.amount;
+        if amount > 0 {
+            let transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.prize_vault.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: leaderboard.to_account_info(),
+                },
+                signer_seeds,
+            );
+            token::transfer(transfer_ctx, amount)?;
+        }
+        
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.prize_vault.to_account_info(),
+                destination: ctx.accounts.admin.to_account_info(),
+                authority: leaderboard.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::close_account(close_ctx)?;
+        
+        emit!(SeasonPrizesSwept {
+            season: leaderboard.season,
+            amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Creates the achievement registry with the default achievements.
+    pub fn initialize_achievement_registry(
+        ctx: Context<InitializeAchievementRegistry>,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.achievement_registry;
+        registry.achievements = AchievementRegistry::defaults();
+        registry.next_id = registry.achievements.len() as u16;
+        registry.bump = ctx.bumps.achievement_registry;
+        
+        Ok(())
+    }
+
+    /// Appends an achievement under the next unused id. Achievements are
+    /// immutable once added, and ids are never reused, so a badge always
+    /// means what it meant when it was awarded.
+    pub fn add_achievement(
+        ctx: Context<UpdateAchievementRegistry>,
+        kind: AchievementKind,
+        threshold: u64,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.achievement_registry;
+        require!(
+            registry.achievements.len() < MAX_ACHIEVEMENTS,
+            AgentError::AchievementRegistryFull
+        );
+        
+        let id = registry.next_id;
+        registry.next_id = id.checked_add(1).ok_or(AgentError::AchievementRegistryFull)?;
+        registry.achievements.push(Achievement {
+            id,
+            kind,
+            threshold,
+        });
+        
+        Ok(())
+    }
+
+    /// Stops awarding an achievement. Badges already awarded are kept.
+    pub fn remove_achievement(ctx: Context<UpdateAchievementRegistry>, id: u16) -> Result<()> {
+        let achievements = &mut ctx.accounts.achievement_registry.achievements;
+        let index = achievements
+            .iter()
+            .position(|achievement| achievement.id == id)
+            .ok_or(AgentError::AchievementNotFound)?;
+        achievements.remove(index);
+        
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EvolutionConfig::LEN,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    pub dmt_mint: Account<'info, Mint>,
+    
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ AgentError::Unauthorized)]
+    pub program: Program<'info, crate::program::AgentEvolution>,
+    
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ AgentError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ AgentError::Unauthorized
+    )]
+    pub config: Account<'info, EvolutionConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAgent<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + OwnerProfile::LEN,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, OwnerProfile>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Agent::LEN,
+        seeds = [b"agent", owner.key().as_ref(), &profile.agent_count.to_le_bytes()],
+        bump
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + OwnedAgent::LEN,
+        seeds = [b"owned_agent", owner.key().as_ref(), &profile.owned_slot_count.to_le_bytes()],
+        bump
+    )]
+    pub owned_slot: Account<'info, OwnedAgent>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = config.dmt_mint,
+        token::authority = owner
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = config.treasury @ AgentError::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FuseAgents<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", primary.creator.as_ref(), &primary.index.to_le_bytes()],
+        bump = primary.bump,
+        has_one = owner
+    )]
+    pub primary: Account<'info, Agent>,
+    
+    #[account(
+        mut,
+        seeds = [b"agent", secondary.creator.as_ref(), &secondary.index.to_le_bytes()],
+        bump = secondary.bump,
+        has_one = owner,
+        constraint = secondary.key() != primary.key() @ AgentError::IncompatibleAgents,
+        close = owner
+    )]
+    pub secondary: Account<'info, Agent>,
+    
+    #[account(
+        mut,
+        seeds = [b"owned_agent", owner.key().as_ref(), &secondary.owner_slot.to_le_bytes()],
+        bump = secondary_slot.bump,
+        close = owner
+    )]
+    pub secondary_slot: Account<'info, OwnedAgent>,
+    
+    /// CHECK: The secondary's rental offer PDA, which must not exist
+    #[account(
+        seeds = [b"rental", secondary.key().as_ref()],
+        bump,
+        constraint = secondary_rental.data_is_empty() @ AgentError::RentalOfferOpen
+    )]
+    pub secondary_rental: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"metadata", primary.key().as_ref()],
+        bump = metadata.bump
+    )]
+    pub metadata: Option<Account<'info, AgentMetadata>>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Prestige<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump,
+        has_one = owner
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    #[account(
+        mut,
+        seeds = [b"metadata", agent.key().as_ref()],
+        bump = metadata.bump
+    )]
+    pub metadata: Option<Account<'info, AgentMetadata>>,
+    
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Evolve<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump,
+        has_one = owner
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + AgentMetadata::LEN,
+        seeds = [b"metadata", agent.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, AgentMetadata>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(task_id: u64, task_xp: u32, performance_boost: u32, skill: Skill, nonce: u64)]
+pub struct CompleteTask<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    /// Replay protection; creation fails if the nonce was already used
+    #[account(
+        init,
+        payer = operator,
+        space = 8 + AttestationNonce::LEN,
+        seeds = [b"attestation_nonce", agent.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub nonce_record: Account<'info, AttestationNonce>,
+    
+    /// CHECK: The instructions sysvar, read to find the Ed25519 attestation
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// The current season's leaderboard; required while a season is running
+    #[account(
+        mut,
+        seeds = [b"leaderboard".as_ref(), &config.current_season.to_le_bytes()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+    
+    #[account(seeds = [b"achievement_registry"], bump = achievement_registry.bump)]
+    pub achievement_registry: Option<Account<'info, AchievementRegistry>>,
+    
+    /// The owner, or the renter during a rental; pays for new badges
+    #[account(mut)]
+    pub operator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAchievementRegistry<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AchievementRegistry::LEN,
+        seeds = [b"achievement_registry"],
+        bump
+    )]
+    pub achievement_registry: Account<'info, AchievementRegistry>,
+    
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ AgentError::Unauthorized)]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAchievementRegistry<'info> {
+    #[account(
+        mut,
+        seeds = [b"achievement_registry"],
+        bump = achievement_registry.bump
+    )]
+    pub achievement_registry: Account<'info, AchievementRegistry>,
+    
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ AgentError::Unauthorized)]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StartSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ AgentError::Unauthorized
+    )]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Leaderboard::LEN,
+        seeds = [b"leaderboard".as_ref(), &(config.current_season + 1).to_le_bytes()],
+        bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    
+    #[account(address = config.dmt_mint)]
+    pub dmt_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = admin,
+        token::mint = dmt_mint,
+        token::authority = leaderboard,
+        seeds = [b"prize_vault", leaderboard.key().as_ref()],
+        bump
+    )]
+    pub prize_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = dmt_mint,
+        token::authority = admin
+    )]
+    pub admin_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"leaderboard".as_ref(), &leaderboard.season.to_le_bytes()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSeasonPrize<'info> {
+    #[account(
+        mut,
+        seeds = [b"leaderboard".as_ref(), &leaderboard.season.to_le_bytes()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    
+    #[account(mut, address = leaderboard.prize_vault @ AgentError::InvalidVault)]
+    pub prize_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump,
+        has_one = owner
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = prize_vault.mint,
+        token::authority = owner
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SweepSeasonPrizes<'info> {
+    #[account(
+        seeds = [b"leaderboard".as_ref(), &leaderboard.season.to_le_bytes()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    
+    #[account(mut, address = leaderboard.prize_vault @ AgentError::InvalidVault)]
+    pub prize_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ AgentError::Unauthorized
+    )]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    #[account(mut, address = config.treasury @ AgentError::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct TrainAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    /// The owner, or the renter during a rental
+    #[account(mut)]
+    pub operator: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = config.dmt_mint,
+        token::authority = operator
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = config.treasury @ AgentError::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = config.dmt_mint,
+        constraint = referrer_token_account.owner != operator.key() @ AgentError::InvalidReferrer
+    )]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut, address = config.dmt_mint)]
+    pub dmt_mint: Account<'info, Mint>,
+    
+    #[account(seeds = [b"achievement_registry"], bump = achievement_registry.bump)]
+    pub achievement_registry: Option<Account<'info, AchievementRegistry>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStakingPool<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + StakingPool::LEN,
+        seeds = [b"staking_pool"],
+        bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ AgentError::Unauthorized)]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    #[account(address = config.dmt_mint)]
+    pub dmt_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = admin,
+        token::mint = dmt_mint,
+        token::authority = staking_pool,
+        seeds = [b"stake_vault", staking_pool.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = admin,
+        token::mint = dmt_mint,
+        token::authority = staking_pool,
+        seeds = [b"reward_vault", staking_pool.key().as_ref()],
+        bump
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateStakingPool<'info> {
+    #[account(mut, seeds = [b"staking_pool"], bump = staking_pool.bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ AgentError::Unauthorized)]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(mut, seeds = [b"staking_pool"], bump = staking_pool.bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(mut, address = staking_pool.reward_vault @ AgentError::InvalidVault)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub funder_token_account: Account<'info, TokenAccount>,
+    
+    pub funder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct StakeAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump,
+        has_one = owner
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + StakePosition::LEN,
+        seeds = [b"stake_position", agent.key().as_ref(), &agent.stake_position_count.to_le_bytes()],
+        bump
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+    
+    #[account(mut, seeds = [b"staking_pool"], bump = staking_pool.bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = staking_pool.dmt_mint,
+        token::authority = owner
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = staking_pool.stake_vault @ AgentError::InvalidVault)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    
+    #[account(seeds = [b"achievement_registry"], bump = achievement_registry.bump)]
+    pub achievement_registry: Option<Account<'info, AchievementRegistry>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump,
+        has_one = owner
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake_position", agent.key().as_ref(), &stake_position.index.to_le_bytes()],
+        bump = stake_position.bump,
+        has_one = agent,
+        has_one = owner
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+    
+    #[account(mut, seeds = [b"staking_pool"], bump = staking_pool.bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = staking_pool.dmt_mint,
+        token::authority = owner
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = staking_pool.stake_vault @ AgentError::InvalidVault)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = staking_pool.reward_vault @ AgentError::InvalidVault)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = staking_pool.dmt_mint)]
+    pub dmt_mint: Account<'info, Mint>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimStakingRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"stake_position", stake_position.agent.as_ref(), &stake_position.index.to_le_bytes()],
+        bump = stake_position.bump,
+        has_one = owner
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+    
+    #[account(mut, seeds = [b"staking_pool"], bump = staking_pool.bump)]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = staking_pool.dmt_mint,
+        token::authority = owner
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = staking_pool.reward_vault @ AgentError::InvalidVault)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct OfferRental<'info> {
+    #[account(
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump,
+        has_one = owner
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Rental::LEN,
+        seeds = [b"rental", agent.key().as_ref()],
+        bump
+    )]
+    pub rental: Account<'info, Rental>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    #[account(address = config.dmt_mint)]
+    pub dmt_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = owner,
+        token::mint = dmt_mint,
+        token::authority = rental,
+        seeds = [b"rental_escrow", rental.key().as_ref()],
+        bump
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RentAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump,
+        // Offers don't survive a sale
+        constraint = agent.owner == rental.owner @ AgentError::RentalUnavailable
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    #[account(
+        mut,
+        seeds = [b"rental", agent.key().as_ref()],
+        bump = rental.bump,
+        has_one = agent
+    )]
+    pub rental: Account<'info, Rental>,
+    
+    #[account(mut, address = rental.escrow_vault @ AgentError::InvalidVault)]
+    pub escrow_vault: Account<'info, TokenAccount>,
+    
+    #[account(constraint = renter.key() != rental.owner @ AgentError::Unauthorized)]
+    pub renter: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = escrow_vault.mint,
+        token::authority = renter
+    )]
+    pub renter_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SettleRental<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    #[account(
+        mut,
+        seeds = [b"rental", agent.key().as_ref()],
+        bump = rental.bump,
+        has_one = agent,
+        has_one = owner
+    )]
+    pub rental: Account<'info, Rental>,
+    
+    #[account(mut, address = rental.escrow_vault @ AgentError::InvalidVault)]
+    pub escrow_vault: Account<'info, TokenAccount>,
+    
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = escrow_vault.mint,
+        token::authority = owner
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRentalOffer<'info> {
+    #[account(
+        mut,
+        seeds = [b"rental", rental.agent.as_ref()],
+        bump = rental.bump,
+        has_one = owner,
+        close = owner
+    )]
+    pub rental: Account<'info, Rental>,
+    
+    #[account(mut, address = rental.escrow_vault @ AgentError::InvalidVault)]
+    pub escrow_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ListAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump,
+        has_one = owner
     )]
     pub agent: Account<'info, Agent>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Listing::LEN,
+        seeds = [b"listing", agent.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    /// CHECK: The agent's rental offer PDA, which must not exist
+    #[account(
+        seeds = [b"rental", agent.key().as_ref()],
+        bump,
+        constraint = rental.data_is_empty() @ AgentError::RentalOfferOpen
+    )]
+    pub rental: UncheckedAccount<'info>,
+    
+    #[account(mut)]
     pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump,
+        constraint = agent.owner == listing.key() @ AgentError::NotListed
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing", agent.key().as_ref()],
+        bump = listing.bump,
+        has_one = seller,
+        close = seller
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, EvolutionConfig>,
+    
+    #[account(address = config.dmt_mint)]
+    pub dmt_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = buyer,
+        token::mint = dmt_mint,
+        token::authority = listing,
+        seeds = [b"listing_escrow", listing.key().as_ref()],
+        bump
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, constraint = buyer.key() != seller.key() @ AgentError::Unauthorized)]
+    pub buyer: Signer<'info>,
+    
+    /// CHECK: Receives the listing's rent; checked against `listing.seller`
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"owned_agent", seller.key().as_ref(), &agent.owner_slot.to_le_bytes()],
+        bump = seller_slot.bump,
+        close = seller
+    )]
+    pub seller_slot: Account<'info, OwnedAgent>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + OwnerProfile::LEN,
+        seeds = [b"profile", buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_profile: Account<'info, OwnerProfile>,
+    
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + OwnedAgent::LEN,
+        seeds = [b"owned_agent", buyer.key().as_ref(), &buyer_profile.owned_slot_count.to_le_bytes()],
+        bump
+    )]
+    pub buyer_slot: Account<'info, OwnedAgent>,
+    
+    #[account(
+        mut,
+        token::mint = config.dmt_mint,
+        token::authority = buyer
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = config.dmt_mint,
+        token::authority = seller
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = config.dmt_mint,
+        token::authority = agent.creator
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = config.treasury @ AgentError::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct DelistAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent.creator.as_ref(), &agent.index.to_le_bytes()],
+        bump = agent.bump,
+        constraint = agent.owner == listing.key() @ AgentError::NotListed
+    )]
+    pub agent: Account<'info, Agent>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing", agent.key().as_ref()],
+        bump = listing.bump,
+        has_one = seller,
+        close = seller
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+}
+
+#[account]
+pub struct Agent {
+    pub owner: Pubkey, // the listing PDA while listed for sale
+    pub creator: Pubkey, // original minter; seeds the PDA and earns royalties
+    pub index: u32, // position in the creator's `OwnerProfile`
+    pub owner_slot: u32, // `OwnedAgent` slot in the owner's profile
+    pub name: String,
+    pub agent_type: AgentType,
+    pub rarity: Rarity,
+    pub backend: ModelBackend,
+    pub level: u8,
+    pub xp: u64,
+    pub prestige_rank: u8,
+    pub season: u32, // season `season_xp` was earned in
+    pub season_xp: u64,
+    pub performance: u32, // earned base, excluding `stake_boost`
+    pub stake_boost: u32, // derived from `total_staked`, recalculated on stake changes
+    pub tasks_completed: u32,
+    pub streak_days: u16, // consecutive days with a completed task
+    pub last_task_day: i64, // day number of the last completed task
+    pub skill_levels: [u8; Skill::COUNT], // indexed by `Skill`
+    pub last_trained_at: i64,
+    pub stage: EvolutionStage,
+    pub created_at: i64,
+    pub last_updated: i64,
+    pub stake_position_count: u32, // index of the next stake position
+    pub active_stake_positions: u32,
+    pub total_staked: u64,
+    pub renter: Pubkey, // default when not rented
+    pub rental_expires_at: i64,
+    pub bump: u8,
+}
+
+impl Agent {
+    pub const LEN: usize = 32 + 32 + 4 + 4 + 64 + 1 + 1 + 1 + 1 + 8 + 1 + 4 + 8 + 4 + 4 + 4 + 2 + 8
+        + Skill::COUNT + 8 + 1 + 8 + 8 + 4 + 4 + 8 + 32 + 8 + 1;
+
+    /// Extends the task streak if the last task was the day before `now`,
+    /// or restarts it.
+    pub fn record_task_day(&mut self, now: i64) {
+        let day = now / SECONDS_PER_DAY;
+        if self.streak_days > 0 && day == self.last_task_day {
+            return;
+        }
+        self.streak_days = if self.streak_days > 0 && day == self.last_task_day + 1 {
+            self.streak_days.saturating_add(1)
+        } else {
+            1
+        };
+        self.last_task_day = day;
+    }
+
+    /// Cosmetic badge for the agent's prestige rank.
+    pub fn prestige_badge(&self) -> PrestigeBadge {
+        match self.prestige_rank {
+            0 => PrestigeBadge::None,
+            1..=2 => PrestigeBadge::Bronze,
+            3..=5 => PrestigeBadge::Silver,
+            6..=9 => PrestigeBadge::Gold,
+            _ => PrestigeBadge::Diamond,
+        }
+    }
+
+    pub fn is_rented(&self, now: i64) -> bool {
+        self.renter != Pubkey::default() && now < self.rental_expires_at
+    }
+
+    /// Whether `key` may use the agent: the renter during a rental, the
+    /// owner otherwise.
+    pub fn is_operator(&self, key: Pubkey, now: i64) -> bool {
+        if self.is_rented(now) {
+            key == self.renter
+        } else {
+            key == self.owner
+        }
+    }
+
+    pub fn skill_level(&self, skill: Skill) -> u8 {
+        self.skill_levels[skill as usize]
+    }
+
+    /// Earned performance plus the current stake boost, capped at
+    /// `MAX_PERFORMANCE`.
+    pub fn effective_performance(&self) -> u32 {
+        self.performance.saturating_add(self.stake_boost).min(MAX_PERFORMANCE)
+    }
+
+    /// Decays performance toward `PERFORMANCE_FLOOR` for the time elapsed
+    /// since `last_updated`, then marks the agent as updated at `now`.
+    /// Values above `MAX_PERFORMANCE` from before the cap existed are
+    /// clamped first.
+    pub fn touch(&mut self, now: i64) {
+        self.performance = self.performance.min(MAX_PERFORMANCE);
+        let elapsed = now - self.last_updated;
+        if elapsed > 0 && self.performance > PERFORMANCE_FLOOR {
+            let excess = (self.performance - PERFORMANCE_FLOOR) as u64;
+            self.performance = PERFORMANCE_FLOOR
+                + decay::half_life_decay(excess, elapsed, PERFORMANCE_HALF_LIFE) as u32;
+        }
+        self.last_updated = now;
+    }
+
+    /// Applies `boost` scaled by the remaining headroom, so repeated boosts
+    /// have diminishing returns and performance never exceeds
+    /// `MAX_PERFORMANCE`.
+    pub fn boost_performance(&mut self, boost: u32) {
+        let headroom = MAX_PERFORMANCE.saturating_sub(self.performance) as u64;
+        let applied = boost.min(MAX_PERFORMANCE) as u64 * headroom / MAX_PERFORMANCE as u64;
+        self.performance += applied as u32;
+    }
+}
+
+#[account]
+pub struct EvolutionConfig {
+    pub admin: Pubkey,
+    pub dmt_mint: Pubkey,
+    pub treasury: Pubkey, // DMT token account receiving mint fees
+    pub mint_prices: [u64; 4], // in DMT base units, indexed by `Rarity`
+    pub stake_boost_curve: StakeBoostCurve,
+    pub stake_boost_unit: u64, // DMT base units per boost point before the curve
+    pub max_stake_boost: u32,
+    pub training_base_cost: u64, // DMT cost of training a skill from level 0
+    pub training_cooldown: i64, // seconds between training sessions
+    pub training_fee_policy: FeePolicy,
+    pub marketplace_fee_bps: u16, // of each sale, to the treasury
+    pub royalty_bps: u16, // of each sale, to the agent's creator
+    pub current_season: u32, // 0 before the first season
+    pub season_ends_at: i64,
+    pub attestors: [Pubkey; MAX_ATTESTORS], // keys trusted to attest task completions
+    pub attestor_count: u8,
+    pub bump: u8,
+}
+
+impl EvolutionConfig {
+    pub const LEN: usize = 32 + 32 + 32 + 8 * 4 + 1 + 8 + 4 + 8 + 8 + FeePolicy::LEN + 2 + 2 + 4
+        + 8 + 32 * MAX_ATTESTORS + 1 + 1;
+
+    pub fn is_attestor(&self, key: &Pubkey) -> bool {
+        self.attestors[..self.attestor_count as usize].contains(key)
+    }
+
+    pub fn mint_price(&self, rarity: Rarity) -> u64 {
+        self.mint_prices[rarity as usize]
+    }
+
+    /// Performance boost for an agent with `total_staked` DMT staked on it.
+    pub fn stake_boost(&self, total_staked: u64) -> u32 {
+        let units = total_staked / self.stake_boost_unit;
+        let boost = match self.stake_boost_curve {
+            StakeBoostCurve::Linear => units,
+            StakeBoostCurve::Sqrt => isqrt(units),
+        };
+        boost.min(self.max_stake_boost as u64) as u32
+    }
+}
+
+/// Split of training fees, in basis points of the fee.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct FeePolicy {
+    pub burn_bps: u16,
+    pub treasury_bps: u16,
+    pub referrer_bps: u16,
+}
+
+impl FeePolicy {
+    pub const LEN: usize = 2 + 2 + 2;
+
+    pub fn is_valid(&self) -> bool {
+        self.burn_bps as u32 + self.treasury_bps as u32 + self.referrer_bps as u32 == 10_000
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum StakeBoostCurve {
+    Linear, // one point per unit staked
+    Sqrt,   // favours many small stakers over one large one
+}
+
+/// One deposit staked on an agent, seeded by the agent and its index.
+#[account]
+pub struct StakePosition {
+    pub agent: Pubkey,
+    pub owner: Pubkey,
+    pub index: u32,
+    pub amount: u64,
+    pub lock_period: i64,
+    pub staked_at: i64,
+    pub reward_debt: u128, // pool rewards already accounted for `amount`
+    pub pending_rewards: u64, // settled but not yet paid out
+    pub bump: u8,
+}
+
+impl StakePosition {
+    pub const LEN: usize = 32 + 32 + 4 + 8 + 8 + 8 + 16 + 8 + 1;
+
+    /// Moves rewards accrued since the last checkpoint into
+    /// `pending_rewards`. `pool` must be updated first.
+    pub fn settle(&mut self, pool: &StakingPool) {
+        let accrued = pool.accrued(self.amount);
+        self.pending_rewards += (accrued - self.reward_debt) as u64;
+        self.reward_debt = accrued;
+    }
+}
+
+/// Pool shared by all stake positions. Rewards are emitted at
+/// `emission_rate` tokens per second and split pro rata by stake using a
+/// reward-per-share accumulator. Both vaults are owned by the pool PDA.
+#[account]
+pub struct StakingPool {
+    pub dmt_mint: Pubkey,
+    pub stake_vault: Pubkey, // holds staked principal
+    pub reward_vault: Pubkey, // funded separately via `fund_rewards`
+    pub emission_rate: u64, // reward base units per second
+    pub acc_reward_per_share: u128, // scaled by `REWARD_PRECISION`
+    pub last_update_at: i64,
+    pub total_staked: u64,
+    pub unallocated_rewards: u64, // funded reward tokens not yet emitted
+    pub early_unstake_penalty_bps: u16, // charged at the start of a lock
+    pub penalty_destination: PenaltyDestination,
+    pub bump: u8,
+}
+
+impl StakingPool {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 16 + 8 + 8 + 8 + 2 + 1 + 1;
+
+    /// Accrues emissions up to `now` into the accumulator, capped at the
+    /// funded rewards not yet emitted.
+    pub fn update(&mut self, now: i64) {
+        if now > self.last_update_at && self.total_staked > 0 {
+            let elapsed = (now - self.last_update_at) as u128;
+            let emitted = (elapsed * self.emission_rate as u128).min(self.unallocated_rewards as u128);
+            self.unallocated_rewards -= emitted as u64;
+            self.acc_reward_per_share += emitted * REWARD_PRECISION / self.total_staked as u128;
+        }
+        self.last_update_at = now.max(self.last_update_at);
+    }
+
+    /// Takes `amount` out of a settled `position`, `penalty` of which is
+    /// forfeited. A penalty kept in the reward pool is shared among the
+    /// other stakers only, so the withdrawer's remaining stake earns none of
+    /// it back; with no other stakers it joins the unallocated rewards.
+    pub fn withdraw(&mut self, position: &mut StakePosition, amount: u64, penalty: u64) {
+        position.amount -= amount;
+        self.total_staked -= amount;
+        if penalty > 0 && self.penalty_destination == PenaltyDestination::RewardPool {
+            let others = self.total_staked - position.amount;
+            if others > 0 {
+                self.acc_reward_per_share += penalty as u128 * REWARD_PRECISION / others as u128;
+            } else {
+                self.unallocated_rewards += penalty;
+            }
+        }
+        // Checkpoint after sharing the penalty so none of it accrues here
+        position.reward_debt = self.accrued(position.amount);
+    }
+
+    /// Total rewards per the accumulator for a stake of `amount`.
+    pub fn accrued(&self, amount: u64) -> u128 {
+        amount as u128 * self.acc_reward_per_share / REWARD_PRECISION
+    }
+
+    /// Penalty for withdrawing `amount` from `position` at `now`, scaled
+    /// linearly by the time left on its lock.
+    pub fn early_unstake_penalty(&self, position: &StakePosition, amount: u64, now: i64) -> u64 {
+        let unlock_at = position.staked_at + position.lock_period;
+        if now >= unlock_at || position.lock_period == 0 {
+            return 0;
+        }
+        let remaining = (unlock_at - now) as u128;
+        (amount as u128 * self.early_unstake_penalty_bps as u128 * remaining
+            / (10_000 * position.lock_period as u128)) as u64
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PenaltyDestination {
+    RewardPool, // shared among remaining stakers
+    Burn,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum AgentType {
+    VisionSync,    // Main Agent - Epic, $5, Grok
+    DomainSync,    // Sub Agent - Common: $1, LLaMA; Rare: $2, ChatGPT/LLaMA 3; Legendary: $10, CrewAI
+}
+
+impl AgentType {
+    /// Whether this agent type can be minted with the given rarity and model
+    /// backend.
+    pub fn supports(&self, rarity: Rarity, backend: ModelBackend) -> bool {
+        matches!(
+            (self, rarity, backend),
+            (AgentType::VisionSync, Rarity::Epic, ModelBackend::Grok)
+                | (AgentType::DomainSync, Rarity::Common, ModelBackend::Llama)
+                | (AgentType::DomainSync, Rarity::Rare, ModelBackend::ChatGpt | ModelBackend::Llama3)
+                | (AgentType::DomainSync, Rarity::Legendary, ModelBackend::CrewAi)
+        )
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rarity {
+    Common,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+impl Rarity {
+    /// XP multiplier applied to completed tasks, in basis points.
+    pub fn xp_multiplier_bps(self) -> u64 {
+        match self {
+            Rarity::Common => 10_000,
+            Rarity::Rare => 12_500,
+            Rarity::Epic => 15_000,
+            Rarity::Legendary => 20_000,
+        }
+    }
+
+    pub fn level_cap(self) -> u8 {
+        match self {
+            Rarity::Common => 40,
+            Rarity::Rare => 60,
+            Rarity::Epic => 80,
+            Rarity::Legendary => MAX_LEVEL,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ModelBackend {
+    Grok,
+    Llama,
+    ChatGpt,
+    Llama3,
+    CrewAi,
+}
+
+/// Top agents by XP earned in one season, seeded by the season number. The
+/// entries are kept sorted by score and frozen once the season is
+/// finalized.
+#[account]
+pub struct Leaderboard {
+    pub season: u32,
+    pub starts_at: i64,
+    pub ends_at: i64,
+    pub entries: [LeaderboardEntry; LEADERBOARD_CAPACITY],
+    pub entry_count: u8,
+    pub finalized: bool,
+    pub prize_vault: Pubkey,
+    pub prizes: [u64; SEASON_PRIZE_SLOTS], // indexed by rank
+    pub prizes_claimed: u8, // bitmask by rank
+    pub bump: u8,
+}
+
+impl Leaderboard {
+    pub const LEN: usize = 4 + 8 + 8 + LeaderboardEntry::LEN * LEADERBOARD_CAPACITY + 1 + 1 + 32
+        + 8 * SEASON_PRIZE_SLOTS + 1 + 1;
+
+    pub fn is_active(&self, now: i64) -> bool {
+        !self.finalized && now >= self.starts_at && now < self.ends_at
+    }
+
+    /// When prizes stop being claimable and can be swept.
+    pub fn claim_deadline(&self) -> i64 {
+        self.ends_at + PRIZE_CLAIM_WINDOW
+    }
+
+    pub fn standings(&self) -> &[LeaderboardEntry] {
+        &self.entries[..self.entry_count as usize]
+    }
+
+    /// Records `agent`'s new season score. An agent not on the board
+    /// replaces the lowest entry once the board is full, if it scores
+    /// higher.
+    pub fn submit(&mut self, agent: Pubkey, score: u64, performance: u32) {
+        let count = self.entry_count as usize;
+        let slot = match self.entries[..count].iter().position(|entry| entry.agent == agent) {
+            Some(slot) => slot,
+            None if count < LEADERBOARD_CAPACITY => {
+                self.entry_count += 1;
+                count
+            }
+            None if score > self.entries[count - 1].score => count - 1,
+            None => return,
+        };
+        self.entries[slot] = LeaderboardEntry {
+            agent,
+            score,
+            performance,
+        };
+        
+        // Scores only grow, so bubble the entry up to its place
+        let mut slot = slot;
+        while slot > 0 && self.entries[slot].score > self.entries[slot - 1].score {
+            self.entries.swap(slot, slot - 1);
+            slot -= 1;
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct LeaderboardEntry {
+    pub agent: Pubkey,
+    pub score: u64, // season XP
+    pub performance: u32, // effective performance when last scored
+}
+
+impl LeaderboardEntry {
+    pub const LEN: usize = 32 + 8 + 4;
+}
+
+/// Message an attestor signs to vouch for a task completion, Borsh-encoded.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TaskAttestation {
+    pub agent: Pubkey,
+    pub task_id: u64,
+    pub task_xp: u32,
+    pub performance_boost: u32,
+    pub skill: Skill,
+    pub nonce: u64,
+}
+
+/// Marks an attestation nonce as used for an agent, seeded by the agent and
+/// the nonce.
+#[account]
+pub struct AttestationNonce {
+    pub agent: Pubkey,
+    pub nonce: u64,
+    pub attestor: Pubkey,
+    pub used_at: i64,
+    pub bump: u8,
+}
+
+impl AttestationNonce {
+    pub const LEN: usize = 32 + 8 + 32 + 8 + 1;
+}
+
+/// Achievements that earn badges, managed by the admin.
+#[account]
+pub struct AchievementRegistry {
+    pub achievements: Vec<Achievement>,
+    pub next_id: u16, // ids are never reused, even after removal
+    pub bump: u8,
+}
+
+impl AchievementRegistry {
+    pub const LEN: usize = 4 + Achievement::LEN * MAX_ACHIEVEMENTS + 2 + 1;
+
+    pub fn defaults() -> Vec<Achievement> {
+        use AchievementKind::*;
+        [
+            (TasksCompleted, 1),   // first task
+            (TasksCompleted, 100), // 100 tasks
+            (StreakDays, 30),      // 30-day streak
+            (Level, 50),           // level 50
+            (StakePositions, 1),   // first stake
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(id, (kind, threshold))| Achievement {
+            id: id as u16,
+            kind,
+            threshold,
+        })
+        .collect()
+    }
+}
+
+/// An achievement earned once the agent's `kind` stat reaches `threshold`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Achievement {
+    pub id: u16, // seeds the badge PDA
+    pub kind: AchievementKind,
+    pub threshold: u64,
+}
+
+impl Achievement {
+    pub const LEN: usize = 2 + 1 + 8;
+
+    pub fn is_met(&self, agent: &Agent) -> bool {
+        let value = match self.kind {
+            AchievementKind::TasksCompleted => agent.tasks_completed as u64,
+            AchievementKind::StreakDays => agent.streak_days as u64,
+            AchievementKind::Level => agent.level as u64,
+            AchievementKind::StakePositions => agent.stake_position_count as u64,
+            AchievementKind::SkillLevel => *agent.skill_levels.iter().max().unwrap_or(&0) as u64,
+        };
+        value >= self.threshold
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AchievementKind {
+    TasksCompleted,
+    StreakDays,
+    Level,
+    StakePositions, // ever opened
+    SkillLevel,     // highest skill
+}
+
+/// An achievement awarded to an agent, seeded by the agent and the
+/// achievement id.
+#[account]
+pub struct Badge {
+    pub agent: Pubkey,
+    pub achievement_id: u16,
+    pub awarded_at: i64,
+    pub bump: u8,
+}
+
+impl Badge {
+    pub const LEN: usize = 32 + 2 + 8 + 1;
+}
+
+/// Per-wallet profile counting the agents minted by `owner`. Agent PDAs
+/// are seeded by the creator and their index in this count.
+#[account]
+pub struct OwnerProfile {
+    pub owner: Pubkey,
+    pub agent_count: u32,
+    pub owned_slot_count: u32, // `OwnedAgent` slots ever opened, including released ones
+    pub bump: u8,
+}
+
+impl OwnerProfile {
+    pub const LEN: usize = 32 + 4 + 4 + 1;
+}
+
+/// Entry in a wallet's index of the agents it holds, seeded by the owner
+/// and a slot number from their `OwnerProfile`. Opened when the owner mints
+/// or buys an agent and closed when it is sold or fused away. An agent
+/// listed for sale keeps its seller's slot until it is bought.
+#[account]
+pub struct OwnedAgent {
+    pub agent: Pubkey,
+    pub bump: u8,
+}
+
+impl OwnedAgent {
+    pub const LEN: usize = 32 + 1;
+}
+
+/// Rental offer for an agent, seeded by the agent, and its current rental
+/// if any. Rent is paid into `escrow_vault` and streams to the owner
+/// linearly over the rental.
+#[account]
+pub struct Rental {
+    pub agent: Pubkey,
+    pub owner: Pubkey,
+    pub escrow_vault: Pubkey,
+    pub price_per_day: u64,
+    pub max_days: u16,
+    pub renter: Pubkey, // default when not rented
+    pub starts_at: i64,
+    pub expires_at: i64,
+    pub total_paid: u64,
+    pub claimed: u64, // rent already paid out to the owner
+    pub bump: u8,
+}
+
+impl Rental {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 2 + 32 + 8 + 8 + 8 + 8 + 1;
+
+    /// Rent streamed to the owner by `now`.
+    pub fn streamed(&self, now: i64) -> u64 {
+        let duration = self.expires_at - self.starts_at;
+        if duration <= 0 || now >= self.expires_at {
+            return self.total_paid;
+        }
+        let elapsed = (now - self.starts_at).max(0) as u128;
+        (self.total_paid as u128 * elapsed / duration as u128) as u64
+    }
 }
 
-#[derive(Accounts)]
-pub struct TrainAgent<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", agent.owner.as_ref()],
-        bump,
-        has_one = owner
-    )]
-    pub agent: Account<'info, Agent>,
+/// An agent listed for sale, seeded by the agent. While it exists the
+/// listing owns the agent.
+#[account]
+pub struct Listing {
+    pub agent: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64, // in DMT base units
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Listing {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 1;
+}
+
+/// Avatar metadata for an agent, seeded by the agent and refreshed on each
+/// evolution.
+#[account]
+pub struct AgentMetadata {
+    pub agent: Pubkey,
+    pub stage: EvolutionStage,
+    pub uri: String,
+    pub level: u8,
+    pub rarity: Rarity,
+    pub backend: ModelBackend,
+    pub skill_levels: [u8; Skill::COUNT],
+    pub prestige_rank: u8,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl AgentMetadata {
+    pub const LEN: usize =
+        32 + 1 + (4 + MAX_METADATA_URI_LEN) + 1 + 1 + 1 + Skill::COUNT + 1 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PrestigeBadge {
+    None,
+    Bronze,  // ranks 1-2
+    Silver,  // ranks 3-5
+    Gold,    // ranks 6-9
+    Diamond, // rank 10
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EvolutionStage {
+    Seed,
+    Adept,
+    Sage,
+}
+
+impl EvolutionStage {
+    pub fn next(self) -> Option<EvolutionStage> {
+        match self {
+            EvolutionStage::Seed => Some(EvolutionStage::Adept),
+            EvolutionStage::Adept => Some(EvolutionStage::Sage),
+            EvolutionStage::Sage => None,
+        }
+    }
+
+    /// Level an agent needs to evolve into this stage.
+    pub fn required_level(self) -> u8 {
+        match self {
+            EvolutionStage::Seed => 1,
+            EvolutionStage::Adept => 15,
+            EvolutionStage::Sage => 35,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Skill {
+    Code,
+    Research,
+    Writing,
+    Analysis,
+}
+
+impl Skill {
+    pub const COUNT: usize = 4;
+
+    /// Skill that must be at least as high as the level being trained.
+    pub fn prerequisite(self) -> Option<Skill> {
+        match self {
+            Skill::Code | Skill::Research => None,
+            Skill::Writing | Skill::Analysis => Some(Skill::Research),
+        }
+    }
+}
+
+pub const MAX_PERFORMANCE: u32 = 10_000;
+pub const MAX_SKILL_LEVEL: u8 = 10;
+pub const MAX_LEVEL: u8 = 100;
+pub const MAX_PRESTIGE_RANK: u8 = 10;
+pub const LEADERBOARD_CAPACITY: usize = 10;
+pub const SEASON_PRIZE_SLOTS: usize = 3;
+pub const MAX_ACHIEVEMENTS: usize = 32;
+pub const MAX_ATTESTORS: usize = 4;
+pub const MAX_METADATA_URI_LEN: usize = 200;
+const TRAINING_BOOST_PER_LEVEL: u32 = 100;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const PRIZE_CLAIM_WINDOW: i64 = 30 * SECONDS_PER_DAY; // after a season ends
+const FUSION_TAX_BPS: u64 = 20_00; // share of combined XP lost on fusion
+const PERFORMANCE_FLOOR: u32 = 100; // Starting performance; decay stops here
+const PERFORMANCE_HALF_LIFE: i64 = 30 * 24 * 60 * 60;
+
+/// XP multiplier for a task done at `skill_level`: 50% when untrained, up
+/// to 200% at `MAX_SKILL_LEVEL`.
+fn skill_xp_multiplier_bps(skill_level: u8) -> u64 {
+    5_000 + skill_level as u64 * 1_500
+}
+
+/// XP multiplier for an agent's prestige rank: +10% per rank.
+fn prestige_xp_multiplier_bps(prestige_rank: u8) -> u64 {
+    10_000 + prestige_rank as u64 * 10_00
+}
+
+/// Integer square root, rounded down.
+fn isqrt(value: u64) -> u64 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = value / 2;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+/// Withdraws `amount` of a position's principal, less any early unstake
+/// penalty, and pays out its rewards. The position is closed once it holds
+/// no stake and nothing is owed; rewards the vault can't cover yet stay
+/// claimable.
+fn withdraw_stake(ctx: Context<UnstakeAgent>, amount: u64) -> Result<()> {
+    let agent = &mut ctx.accounts.agent;
+    let pool = &mut ctx.accounts.staking_pool;
+    let position = &mut ctx.accounts.stake_position;
+    let current_time = Clock::get()?.unix_timestamp;
     
-    #[account(mut)]
-    pub owner: Signer<'info>,
+    require!(position.amount > 0, AgentError::NotStaked);
+    require!(!agent.is_rented(current_time), AgentError::AgentRented);
     
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pool.update(current_time);
+    position.settle(pool);
     
-    #[account(mut)]
-    pub training_fee_account: Account<'info, TokenAccount>,
+    let penalty = pool.early_unstake_penalty(position, amount, current_time);
+    let principal_returned = amount - penalty;
     
-    pub token_program: Program<'info, Token>,
-}
-
-#[derive(Accounts)]
-pub struct StakeAgent<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", agent.owner.as_ref()],
-        bump,
-        has_one = owner
-    )]
-    pub agent: Account<'info, Agent>,
+    let bump = [pool.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"staking_pool", &bump]];
     
-    #[account(
-        init_if_needed,
-        payer = owner,
-        space = 8 + StakingInfo::LEN,
-        seeds = [b"staking", agent.key().as_ref()],
-        bump
-    )]
-    pub staking_info: Account<'info, StakingInfo>,
+    // Return principal from the stake vault, signed by the pool PDA
+    if principal_returned > 0 {
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.stake_vault.to_account_info(),
+                to: ctx.accounts.user_token_account.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::transfer(transfer_ctx, principal_returned)?;
+    }
     
-    #[account(mut)]
-    pub owner: Signer<'info>,
+    // Route the penalty
+    if penalty > 0 {
+        match pool.penalty_destination {
+            PenaltyDestination::RewardPool => {
+                let transfer_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.stake_vault.to_account_info(),
+                        to: ctx.accounts.reward_vault.to_account_info(),
+                        authority: pool.to_account_info(),
+                    },
+                    signer_seeds,
+                );
+                token::transfer(transfer_ctx, penalty)?;
+            }
+            PenaltyDestination::Burn => {
+                let burn_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: ctx.accounts.dmt_mint.to_account_info(),
+                        from: ctx.accounts.stake_vault.to_account_info(),
+                        authority: pool.to_account_info(),
+                    },
+                    signer_seeds,
+                );
+                token::burn(burn_ctx, penalty)?;
+            }
+        }
+    }
     
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+    let rewards = pay_rewards(
+        position,
+        &ctx.accounts.reward_vault,
+        ctx.accounts.user_token_account.to_account_info(),
+        pool.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        signer_seeds,
+    )?;
     
-    #[account(mut)]
-    pub staking_pool: Account<'info, TokenAccount>,
+    pool.withdraw(position, amount, penalty);
+    agent.total_staked -= amount;
+    if position.amount == 0 {
+        agent.active_stake_positions -= 1;
+    }
+    agent.stake_boost = ctx.accounts.config.stake_boost(agent.total_staked);
     
-    pub token_program: Program<'info, Token>,
+    emit!(AgentUnstaked {
+        agent: agent.key(),
+        position: position.key(),
+        principal_returned,
+        rewards,
+        penalty,
+        remaining_stake: position.amount,
+        total_staked: agent.total_staked,
+        new_performance: agent.effective_performance(),
+    });
+    
+    if position.amount == 0 && position.pending_rewards == 0 {
+        ctx.accounts.stake_position.close(ctx.accounts.owner.to_account_info())?;
+    }
+    
+    Ok(())
 }
 
-#[derive(Accounts)]
-pub struct UnstakeAgent<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", agent.owner.as_ref()],
-        bump,
-        has_one = owner
-    )]
-    pub agent: Account<'info, Agent>,
+/// Pays the owner rent streamed up to `now` and not yet claimed, returning
+/// the amount paid.
+fn pay_streamed_rent<'info>(
+    rental: &mut Account<'info, Rental>,
+    escrow_vault: &Account<'info, TokenAccount>,
+    owner_token_account: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    now: i64,
+) -> Result<u64> {
+    let amount = rental.streamed(now) - rental.claimed;
+    if amount > 0 {
+        let bump = [rental.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"rental", rental.agent.as_ref(), &bump]];
+        let transfer_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: escrow_vault.to_account_info(),
+                to: owner_token_account.to_account_info(),
+                authority: rental.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::transfer(transfer_ctx, amount)?;
+        rental.claimed += amount;
+    }
+    Ok(amount)
+}
+
+/// Checks that the instruction before this one is an Ed25519 program
+/// instruction verifying a registered attestor's signature over `message`,
+/// and returns the attestor.
+fn verify_attestation(
+    instructions: &AccountInfo,
+    config: &EvolutionConfig,
+    message: &[u8],
+) -> Result<Pubkey> {
+    let current_index = load_current_index_checked(instructions)?;
+    require!(current_index > 0, AgentError::MissingAttestation);
+    let ix = load_instruction_at_checked(current_index as usize - 1, instructions)?;
+    require!(
+        ix.program_id == ed25519_program::ID,
+        AgentError::MissingAttestation
+    );
     
-    #[account(
-        mut,
-        seeds = [b"staking", agent.key().as_ref()],
-        bump,
-        has_one = owner
-    )]
-    pub staking_info: Account<'info, StakingInfo>,
+    // Layout: [count: u8, padding: u8] followed by one 14-byte offsets
+    // struct, with the key, signature and message inlined in the same
+    // instruction (instruction indices of u16::MAX)
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, AgentError::InvalidAttestation);
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let signature_ix = read_u16(4);
+    let public_key_offset = read_u16(6) as usize;
+    let public_key_ix = read_u16(8);
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+    let message_ix = read_u16(14);
+    require!(
+        signature_ix == u16::MAX && public_key_ix == u16::MAX && message_ix == u16::MAX,
+        AgentError::InvalidAttestation
+    );
     
-    #[account(mut)]
-    pub owner: Signer<'info>,
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(AgentError::InvalidAttestation)?;
+    let signed_message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(AgentError::InvalidAttestation)?;
+    require!(signed_message == message, AgentError::InvalidAttestation);
     
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+    let attestor = Pubkey::try_from(public_key).map_err(|_| AgentError::InvalidAttestation)?;
+    require!(config.is_attestor(&attestor), AgentError::UnknownAttestor);
+    Ok(attestor)
+}
+
+/// Creates a badge PDA for each registry achievement the agent meets.
+/// `badge_accounts` come from `remaining_accounts` and hold the badge for
+/// each registry achievement in registry order, as returned by
+/// `client::badge_addresses`; a prefix may be passed. Badges already
+/// awarded are skipped before deriving their address, so only badges being
+/// created pay for the derivation.
+fn award_badges<'info>(
+    agent: &Account<'info, Agent>,
+    registry: &AchievementRegistry,
+    badge_accounts: &[AccountInfo<'info>],
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    now: i64,
+) -> Result<()> {
+    let agent_key = agent.key();
+    for (achievement, badge_info) in registry.achievements.iter().zip(badge_accounts) {
+        if badge_info.owner == &crate::ID || !achievement.is_met(agent) {
+            continue; // already awarded or not yet earned
+        }
+        
+        let id = achievement.id.to_le_bytes();
+        let (address, bump) =
+            Pubkey::find_program_address(&[b"badge", agent_key.as_ref(), &id], &crate::ID);
+        require_keys_eq!(badge_info.key(), address, AgentError::InvalidBadgeAccount);
+        
+        let bump_seed = [bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"badge", agent_key.as_ref(), &id, &bump_seed]];
+        create_program_account(
+            payer,
+            badge_info,
+            system_program,
+            8 + Badge::LEN,
+            signer_seeds,
+        )?;
+        let badge = Badge {
+            agent: agent_key,
+            achievement_id: achievement.id,
+            awarded_at: now,
+            bump,
+        };
+        badge.try_serialize(&mut &mut badge_info.try_borrow_mut_data()?[..])?;
+        
+        emit!(BadgeAwarded {
+            agent: agent_key,
+            badge: address,
+            achievement_id: achievement.id,
+            awarded_at: now,
+        });
+    }
+    Ok(())
+}
+
+/// Creates a rent-exempt PDA owned by this program, like Anchor's `init`,
+/// including when the address was already sent lamports.
+fn create_program_account<'info>(
+    payer: &Signer<'info>,
+    target: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let current_lamports = target.lamports();
+    if current_lamports == 0 {
+        let create_ctx = CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::CreateAccount {
+                from: payer.to_account_info(),
+                to: target.clone(),
+            },
+            signer_seeds,
+        );
+        return system_program::create_account(create_ctx, rent, space as u64, &crate::ID);
+    }
     
-    #[account(mut)]
-    pub staking_pool: Account<'info, TokenAccount>,
+    let shortfall = rent.saturating_sub(current_lamports);
+    if shortfall > 0 {
+        let transfer_ctx = CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: payer.to_account_info(),
+                to: target.clone(),
+            },
+        );
+        system_program::transfer(transfer_ctx, shortfall)?;
+    }
+    let allocate_ctx = CpiContext::new_with_signer(
+        system_program.to_account_info(),
+        system_program::Allocate {
+            account_to_allocate: target.clone(),
+        },
+        signer_seeds,
+    );
+    system_program::allocate(allocate_ctx, space as u64)?;
+    let assign_ctx = CpiContext::new_with_signer(
+        system_program.to_account_info(),
+        system_program::Assign {
+            account_to_assign: target.clone(),
+        },
+        signer_seeds,
+    );
+    system_program::assign(assign_ctx, &crate::ID)
+}
+
+/// Scale of `StakingPool::acc_reward_per_share`.
+const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Pays as much of the position's pending rewards as the reward vault holds
+/// and returns the amount paid. The transfer is signed by the pool PDA.
+fn pay_rewards<'info>(
+    position: &mut StakePosition,
+    reward_vault: &Account<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    pool: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    let amount = position.pending_rewards.min(reward_vault.amount);
+    if amount > 0 {
+        let transfer_ctx = CpiContext::new_with_signer(
+            token_program,
+            Transfer {
+                from: reward_vault.to_account_info(),
+                to,
+                authority: pool,
+            },
+            signer_seeds,
+        );
+        token::transfer(transfer_ctx, amount)?;
+        position.pending_rewards -= amount;
+    }
+    Ok(amount)
+}
+
+fn calculate_level(xp: u64) -> u8 {
+    // Level calculation: each level requires more XP
+    // Level 1: 0 XP, Level 2: 100 XP, Level 3: 300 XP, etc.
+    let mut level: u64 = 1;
+    let mut next_level_xp: u64 = 100;
     
-    /// CHECK: This is the staking pool authority
-    pub staking_authority: UncheckedAccount<'info>,
+    while xp >= next_level_xp && level < MAX_LEVEL as u64 {
+        level += 1;
+        next_level_xp += level * 100;
+    }
     
-    pub token_program: Program<'info, Token>,
+    level as u8
 }
 
-#[account]
-pub struct Agent {
+#[event]
+pub struct AgentMinted {
+    pub agent: Pubkey,
     pub owner: Pubkey,
-    pub name: String,
-    pub agent_type: AgentType,
-    pub level: u8,
-    pub xp: u32,
-    pub performance: u32,
-    pub tasks_completed: u32,
-    pub created_at: i64,
-    pub last_updated: i64,
+    pub rarity: Rarity,
+    pub backend: ModelBackend,
+    pub price: u64,
 }
 
-impl Agent {
-    pub const LEN: usize = 32 + 64 + 1 + 1 + 4 + 4 + 4 + 8 + 8;
+#[event]
+pub struct RentalOffered {
+    pub agent: Pubkey,
+    pub rental: Pubkey,
+    pub price_per_day: u64,
+    pub max_days: u16,
 }
 
-#[account]
-pub struct StakingInfo {
+#[event]
+pub struct AgentRented {
+    pub agent: Pubkey,
+    pub renter: Pubkey,
+    pub days: u16,
+    pub rent: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct RentalPaymentClaimed {
     pub agent: Pubkey,
     pub owner: Pubkey,
-    pub stake_amount: u64,
-    pub lock_period: i64,
-    pub staked_at: i64,
-    pub is_active: bool,
+    pub amount: u64,
 }
 
-impl StakingInfo {
-    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 1;
+#[event]
+pub struct RentalEnded {
+    pub agent: Pubkey,
+    pub renter: Pubkey,
+    pub amount: u64, // final payment to the owner
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum AgentType {
-    VisionSync,    // Main Agent - Epic, $5, Grok
-    DomainSync,    // Sub Agent - Common: $1, LLaMA; Rare: $2, ChatGPT/LLaMA 3; Legendary: $10, CrewAI
+#[event]
+pub struct AgentListed {
+    pub agent: Pubkey,
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
 }
 
-fn calculate_level(xp: u32) -> u8 {
-    // Level calculation: each level requires more XP
-    // Level 1: 0 XP, Level 2: 100 XP, Level 3: 300 XP, etc.
-    let mut level = 1;
-    let mut required_xp = 0;
-    
-    while xp >= required_xp && level < 100 {
-        level += 1;
-        required_xp += level * 100;
-    }
-    
-    level.min(100)
+#[event]
+pub struct AgentSold {
+    pub agent: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub marketplace_fee: u64,
+    pub royalty: u64,
+}
+
+#[event]
+pub struct AgentDelisted {
+    pub agent: Pubkey,
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct AgentsFused {
+    pub owner: Pubkey,
+    pub primary: Pubkey,
+    pub secondary: Pubkey, // closed
+    pub xp: u64,
+    pub fusion_tax: u64,
+    pub rarity: Rarity,
+    pub level: u8,
+}
+
+#[event]
+pub struct TaskAttested {
+    pub agent: Pubkey,
+    pub attestor: Pubkey,
+    pub task_id: u64,
+    pub nonce: u64,
+    pub earned_xp: u64,
+}
+
+#[event]
+pub struct BadgeAwarded {
+    pub agent: Pubkey,
+    pub badge: Pubkey,
+    pub achievement_id: u16,
+    pub awarded_at: i64,
+}
+
+#[event]
+pub struct SeasonStarted {
+    pub season: u32,
+    pub ends_at: i64,
+    pub prizes: [u64; SEASON_PRIZE_SLOTS],
+}
+
+#[event]
+pub struct SeasonFinalized {
+    pub season: u32,
+    pub standings: Vec<LeaderboardEntry>,
+}
+
+#[event]
+pub struct SeasonPrizeClaimed {
+    pub season: u32,
+    pub agent: Pubkey,
+    pub rank: u8,
+    pub amount: u64,
+}
+
+#[event]
+pub struct SeasonPrizesSwept {
+    pub season: u32,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AgentPrestiged {
+    pub agent: Pubkey,
+    pub prestige_rank: u8,
+    pub badge: PrestigeBadge,
+}
+
+#[event]
+pub struct AgentEvolved {
+    pub agent: Pubkey,
+    pub metadata: Pubkey,
+    pub stage: EvolutionStage,
+    pub level: u8,
+    pub uri: String,
 }
 
 #[event]
 pub struct AgentLevelUp {
     pub agent: Pubkey,
     pub new_level: u8,
-    pub xp: u32,
+    pub xp: u64,
 }
 
 #[event]
 pub struct AgentTrained {
     pub agent: Pubkey,
+    pub skill: Skill,
+    pub new_skill_level: u8,
     pub training_cost: u64,
+    pub burned: u64,
+    pub to_treasury: u64,
+    pub to_referrer: u64,
+    pub referrer: Option<Pubkey>, // referrer's token account
     pub new_performance: u32,
 }
 
+#[event]
+pub struct EmissionRateUpdated {
+    pub staking_pool: Pubkey,
+    pub emission_rate: u64,
+}
+
+#[event]
+pub struct PenaltyPolicyUpdated {
+    pub staking_pool: Pubkey,
+    pub early_unstake_penalty_bps: u16,
+    pub penalty_destination: PenaltyDestination,
+}
+
+#[event]
+pub struct RewardsFunded {
+    pub staking_pool: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct StakingRewardsClaimed {
+    pub agent: Pubkey,
+    pub position: Pubkey,
+    pub amount: u64,
+}
+
 #[event]
 pub struct AgentStaked {
     pub agent: Pubkey,
+    pub position: Pubkey,
     pub stake_amount: u64,
     pub lock_period: i64,
+    pub total_staked: u64,
     pub new_performance: u32,
 }
 
 #[event]
 pub struct AgentUnstaked {
     pub agent: Pubkey,
-    pub stake_returned: u64,
-    pub rewards_earned: u64,
+    pub position: Pubkey,
+    pub principal_returned: u64,
+    pub rewards: u64,
+    pub penalty: u64,
+    pub remaining_stake: u64,
+    pub total_staked: u64,
+    pub new_performance: u32,
 }
 
 #[error_code]
 pub enum AgentError {
     #[msg("Agent is not staked")]
     NotStaked,
+    #[msg("Stake amount must be greater than zero")]
+    InvalidStakeAmount,
+    #[msg("Lock period cannot be negative")]
+    InvalidLockPeriod,
     #[msg("Lock period has not expired")]
     LockPeriodNotExpired,
-} 
\ No newline at end of file
+    #[msg("Rarity or model backend is not available for this agent type")]
+    InvalidRarity,
+    #[msg("Token account is not the configured treasury")]
+    InvalidTreasury,
+    #[msg("Token account is not the staking pool's vault")]
+    InvalidVault,
+    #[msg("Penalty cannot exceed 100%")]
+    InvalidPenalty,
+    #[msg("Stake boost unit must be nonzero")]
+    InvalidStakeBoostCurve,
+    #[msg("Cooldown cannot be negative")]
+    InvalidCooldown,
+    #[msg("Agent is still cooling down from its last training session")]
+    TrainingCooldown,
+    #[msg("Skill is already at the maximum level")]
+    SkillMaxed,
+    #[msg("Prerequisite skill level is too low")]
+    SkillPrerequisiteNotMet,
+    #[msg("Fee policy shares must sum to 10000 bps")]
+    InvalidFeePolicy,
+    #[msg("Agents cannot be trained with a self-referral")]
+    InvalidReferrer,
+    #[msg("Agent has not reached the level for its next stage")]
+    EvolutionLocked,
+    #[msg("Agent is already at its final stage")]
+    MaxStageReached,
+    #[msg("Metadata URI is too long")]
+    MetadataUriTooLong,
+    #[msg("Agent still has stake outstanding")]
+    AgentHasStake,
+    #[msg("Agents cannot be fused together")]
+    IncompatibleAgents,
+    #[msg("Price must be greater than zero")]
+    InvalidPrice,
+    #[msg("Agent is not listed")]
+    NotListed,
+    #[msg("Listing price does not match the expected price")]
+    PriceMismatch,
+    #[msg("Agent is rented")]
+    AgentRented,
+    #[msg("Agent is not rented")]
+    NotRented,
+    #[msg("Rental has not expired yet")]
+    RentalNotExpired,
+    #[msg("Rental duration is out of range")]
+    InvalidRentalDuration,
+    #[msg("Rental offer is no longer valid")]
+    RentalUnavailable,
+    #[msg("Agent has an open rental offer")]
+    RentalOfferOpen,
+    #[msg("Agent must be at its level cap to prestige")]
+    PrestigeLocked,
+    #[msg("Agent is already at the maximum prestige rank")]
+    MaxPrestigeReached,
+    #[msg("XP overflow")]
+    XpOverflow,
+    #[msg("Season duration or prizes are invalid")]
+    InvalidSeason,
+    #[msg("Season is not active")]
+    SeasonNotActive,
+    #[msg("The running season's leaderboard is required")]
+    LeaderboardRequired,
+    #[msg("Season has not ended yet")]
+    SeasonNotEnded,
+    #[msg("Season is already finalized")]
+    SeasonFinalized,
+    #[msg("No prize for this agent and rank")]
+    NoPrize,
+    #[msg("Prize already claimed")]
+    PrizeAlreadyClaimed,
+    #[msg("Season prize claim window has closed")]
+    PrizeClaimClosed,
+    #[msg("Season prize claim window is still open")]
+    PrizeClaimOpen,
+    #[msg("Achievement registry is full")]
+    AchievementRegistryFull,
+    #[msg("Achievement not found")]
+    AchievementNotFound,
+    #[msg("Badge account does not match its achievement")]
+    InvalidBadgeAccount,
+    #[msg("Task completion must follow an Ed25519 attestation instruction")]
+    MissingAttestation,
+    #[msg("Attestation does not match this task completion")]
+    InvalidAttestation,
+    #[msg("Attestor is not registered")]
+    UnknownAttestor,
+    #[msg("Attestor is already registered")]
+    AttestorAlreadyRegistered,
+    #[msg("Too many attestors registered")]
+    TooManyAttestors,
+    #[msg("Unauthorized")]
+    Unauthorized,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(total_staked: u64) -> StakingPool {
+        StakingPool {
+            dmt_mint: Pubkey::default(),
+            stake_vault: Pubkey::default(),
+            reward_vault: Pubkey::default(),
+            emission_rate: 0,
+            acc_reward_per_share: 0,
+            last_update_at: 0,
+            total_staked,
+            unallocated_rewards: 0,
+            early_unstake_penalty_bps: 50_00,
+            penalty_destination: PenaltyDestination::RewardPool,
+            bump: 0,
+        }
+    }
+
+    fn position(amount: u64, lock_period: i64) -> StakePosition {
+        StakePosition {
+            agent: Pubkey::default(),
+            owner: Pubkey::default(),
+            index: 0,
+            amount,
+            lock_period,
+            staked_at: 0,
+            reward_debt: 0,
+            pending_rewards: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn partial_early_unstake_does_not_refund_own_penalty() {
+        let mut pool = pool(1_000);
+        let mut staker = position(1_000, 100);
+
+        let penalty = pool.early_unstake_penalty(&staker, 999, 0);
+        assert!(penalty > 0);
+        pool.withdraw(&mut staker, 999, penalty);
+
+        // Claiming afterwards pays nothing back from the penalty
+        pool.update(1);
+        staker.settle(&pool);
+        assert_eq!(staker.pending_rewards, 0);
+        assert_eq!(pool.unallocated_rewards, penalty);
+    }
+
+    #[test]
+    fn early_unstake_penalty_goes_to_other_stakers() {
+        let mut pool = pool(2_000);
+        let mut staker = position(1_000, 100);
+        let mut other = position(1_000, 0);
+
+        let penalty = pool.early_unstake_penalty(&staker, 999, 0);
+        pool.withdraw(&mut staker, 999, penalty);
+
+        staker.settle(&pool);
+        other.settle(&pool);
+        assert_eq!(staker.pending_rewards, 0);
+        assert_eq!(other.pending_rewards, penalty);
+    }
+}
@@ -0,0 +1,30 @@
+//! Off-chain helpers for clients of the agent program.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use anchor_lang::solana_program::hash::Hasher;
+
+use crate::AgentTask;
+
+/// Computes the sha256 digest of a deliverable, as committed by
+/// `complete_task`.
+pub fn hash_result(path: impl AsRef<Path>) -> io::Result<[u8; 32]> {
+    let mut file = File::open(path)?;
+    let mut hasher = Hasher::default();
+    let mut buf = [0u8; 8192];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.hash(&buf[..read]);
+    }
+    Ok(hasher.result().to_bytes())
+}
+
+/// Checks a local file against the result hash committed on `task`.
+pub fn verify_result(path: impl AsRef<Path>, task: &AgentTask) -> io::Result<bool> {
+    Ok(hash_result(path)? == task.result_hash)
+}
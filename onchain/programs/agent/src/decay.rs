@@ -0,0 +1,16 @@
+//! Time decay shared by the agent programs.
+
+/// Halves `value` every `half_life` seconds, interpolating linearly within a
+/// half-life.
+pub fn half_life_decay(value: u64, elapsed: i64, half_life: i64) -> u64 {
+    if elapsed <= 0 {
+        return value;
+    }
+    let halvings = elapsed / half_life;
+    if halvings >= 64 {
+        return 0;
+    }
+    let value = (value >> halvings) as u128;
+    let remainder = (elapsed % half_life) as u128;
+    (value - value * remainder / (2 * half_life as u128)) as u64
+}
@@ -0,0 +1,78 @@
+//! Off-chain helpers for clients of the evolution program.
+
+use anchor_lang::prelude::{AnchorSerialize, Pubkey};
+
+use crate::{AchievementRegistry, OwnedAgent, OwnerProfile, TaskAttestation, ID};
+
+pub fn profile_address(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"profile", owner.as_ref()], &ID).0
+}
+
+pub fn agent_address(creator: &Pubkey, index: u32) -> Pubkey {
+    Pubkey::find_program_address(&[b"agent", creator.as_ref(), &index.to_le_bytes()], &ID).0
+}
+
+/// Addresses of every agent minted by the profile's owner, in mint
+/// order. Fetch them with `getMultipleAccounts`; fused agents are
+/// closed and come back empty.
+pub fn agent_addresses(profile: &OwnerProfile) -> Vec<Pubkey> {
+    (0..profile.agent_count)
+        .map(|index| agent_address(&profile.owner, index))
+        .collect()
+}
+
+pub fn owned_agent_address(owner: &Pubkey, slot: u32) -> Pubkey {
+    Pubkey::find_program_address(&[b"owned_agent", owner.as_ref(), &slot.to_le_bytes()], &ID).0
+}
+
+/// Addresses of every slot in the profile owner's index of held agents,
+/// whether minted or bought. Fetch them with `getMultipleAccounts`;
+/// released slots come back empty.
+pub fn owned_agent_addresses(profile: &OwnerProfile) -> Vec<Pubkey> {
+    (0..profile.owned_slot_count)
+        .map(|slot| owned_agent_address(&profile.owner, slot))
+        .collect()
+}
+
+/// The agents named by the owner's fetched index slots.
+pub fn owned_agents<'a>(slots: impl IntoIterator<Item = &'a OwnedAgent>) -> Vec<Pubkey> {
+    slots.into_iter().map(|slot| slot.agent).collect()
+}
+
+/// Bytes an attestor signs to attest a task completion. Submit the
+/// signature in an Ed25519 program instruction placed directly before
+/// `complete_task`.
+pub fn attestation_message(attestation: &TaskAttestation) -> Vec<u8> {
+    attestation
+        .try_to_vec()
+        .expect("serializing to a Vec cannot fail")
+}
+
+pub fn attestation_nonce_address(agent: &Pubkey, nonce: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"attestation_nonce", agent.as_ref(), &nonce.to_le_bytes()],
+        &ID,
+    )
+    .0
+}
+
+pub fn badge_address(agent: &Pubkey, achievement_id: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"badge", agent.as_ref(), &achievement_id.to_le_bytes()],
+        &ID,
+    )
+    .0
+}
+
+/// Badge addresses for every registered achievement, keyed by
+/// achievement id, in registry order. Fetch them for a profile page;
+/// empty accounts are badges not yet earned. Passing the addresses in
+/// this order as remaining accounts to `complete_task`, `stake_agent` or
+/// `train_agent` lets those instructions award the unearned ones.
+pub fn badge_addresses(agent: &Pubkey, registry: &AchievementRegistry) -> Vec<(u16, Pubkey)> {
+    registry
+        .achievements
+        .iter()
+        .map(|achievement| (achievement.id, badge_address(agent, achievement.id)))
+        .collect()
+}
@@ -1,6 +1,11 @@
 use anchor_lang::prelude::*;
+use anchor_lang::{system_program, Discriminator};
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
+#[cfg(not(target_os = "solana"))]
+pub mod client;
+mod decay;
+
 declare_id!("AgentSys11111111111111111111111111111111111");
 
 #[program]
@@ -13,6 +18,8 @@ pub mod decentramind_agent {
         role: String,
         bump: u8,
     ) -> Result<()> {
+        ctx.accounts.config.require_active(PausableInstruction::InitializeMasterAgent)?;
+
         let master_agent = &mut ctx.accounts.master_agent;
         master_agent.authority = ctx.accounts.authority.key();
         master_agent.name = name;
@@ -23,6 +30,9 @@ pub mod decentramind_agent {
         master_agent.created_at = Clock::get()?.unix_timestamp;
         master_agent.last_active_at = Clock::get()?.unix_timestamp;
         master_agent.bump = bump;
+        master_agent.version = MasterAgent::VERSION;
+        master_agent.event_seq = 0;
+        master_agent.usage = EpochUsage::default();
         
         // Initialize dashboard KPIs
         master_agent.dashboard = DashboardKPIs {
@@ -35,8 +45,11 @@ pub mod decentramind_agent {
             system_uptime: 100_00, // 100.00% in basis points
         };
 
+        let current_time = Clock::get()?.unix_timestamp;
+        let master_key = master_agent.key();
         emit!(MasterAgentInitialized {
-            master_agent: master_agent.key(),
+            header: master_agent.next_event_header(master_key, current_time),
+            master_agent: master_key,
             authority: ctx.accounts.authority.key(),
             name: master_agent.name.clone(),
         });
@@ -51,6 +64,8 @@ pub mod decentramind_agent {
         purpose: String,
         bump: u8,
     ) -> Result<()> {
+        ctx.accounts.config.require_active(PausableInstruction::CreateSubAgent)?;
+
         let sub_agent = &mut ctx.accounts.sub_agent;
         let master_agent = &mut ctx.accounts.master_agent;
 
@@ -72,16 +87,30 @@ pub mod decentramind_agent {
             average_completion_time: 0,
             success_rate: 0,
         };
+        sub_agent.reputation = Reputation {
+            score_bps: REPUTATION_NEUTRAL_BPS,
+            successes: 0,
+            failures: 0,
+            on_time: 0,
+            disputes: 0,
+            last_completion_day: 0,
+            updated_at: Clock::get()?.unix_timestamp,
+        };
+        sub_agent.usage = EpochUsage::default();
         sub_agent.created_at = Clock::get()?.unix_timestamp;
         sub_agent.last_active_at = Clock::get()?.unix_timestamp;
         sub_agent.bump = bump;
+        sub_agent.version = SubAgent::VERSION;
 
         // Update master agent stats
         master_agent.dashboard.total_agents += 1;
         master_agent.dashboard.active_agents += 1;
         master_agent.last_active_at = Clock::get()?.unix_timestamp;
 
+        let current_time = Clock::get()?.unix_timestamp;
+        let master_key = master_agent.key();
         emit!(SubAgentCreated {
+            header: master_agent.next_event_header(master_key, current_time),
             sub_agent: sub_agent.key(),
             master_agent: master_agent.key(),
             agent_type,
@@ -100,10 +129,18 @@ pub mod decentramind_agent {
         tags: Vec<String>,
         bump: u8,
     ) -> Result<()> {
+        ctx.accounts.config.require_active(PausableInstruction::AssignTask)?;
+
+        let config = &ctx.accounts.config;
         let task = &mut ctx.accounts.task;
         let sub_agent = &mut ctx.accounts.sub_agent;
         let master_agent = &mut ctx.accounts.master_agent;
 
+        // Enforce per-epoch assignment limits
+        let epoch = Clock::get()?.epoch;
+        sub_agent.usage.record_assignment(epoch, xp_reward, &config.sub_agent_limits)?;
+        master_agent.usage.record_assignment(epoch, xp_reward, &config.master_limits)?;
+
         task.assigned_to = sub_agent.key();
         task.title = title;
         task.description = description;
@@ -114,12 +151,22 @@ pub mod decentramind_agent {
         task.created_at = Clock::get()?.unix_timestamp;
         task.updated_at = Clock::get()?.unix_timestamp;
         task.bump = bump;
+        task.version = AgentTask::VERSION;
+        task.parent = None;
+        task.children_total = 0;
+        task.children_finished = 0;
+        task.delegated_xp = 0;
+        task.result_hash = [0; 32];
+        task.result_uri = String::new();
 
         // Update agent activity
         sub_agent.last_active_at = Clock::get()?.unix_timestamp;
         master_agent.last_active_at = Clock::get()?.unix_timestamp;
 
+        let current_time = Clock::get()?.unix_timestamp;
+        let master_key = master_agent.key();
         emit!(TaskAssigned {
+            header: master_agent.next_event_header(master_key, current_time),
             task: task.key(),
             sub_agent: sub_agent.key(),
             title: task.title.clone(),
@@ -129,20 +176,108 @@ pub mod decentramind_agent {
         Ok(())
     }
 
-    pub fn complete_task(ctx: Context<CompleteTask>) -> Result<()> {
+    /// Splits off part of a task to a sibling sub-agent, carving the child's
+    /// XP reward out of the parent's.
+    pub fn delegate_task(
+        ctx: Context<DelegateTask>,
+        title: String,
+        description: String,
+        priority: TaskPriority,
+        xp_reward: u64,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        ctx.accounts.config.require_active(PausableInstruction::DelegateTask)?;
+
+        let config = &ctx.accounts.config;
+        let task = &mut ctx.accounts.task;
+        let parent_task = &mut ctx.accounts.parent_task;
+        let delegate = &mut ctx.accounts.delegate;
+        let master_agent = &mut ctx.accounts.master_agent;
+
+        require!(parent_task.status == TaskStatus::Pending || parent_task.status == TaskStatus::InProgress,
+                AgentError::TaskAlreadyCompleted);
+        require!(xp_reward <= parent_task.xp_reward, AgentError::InsufficientTaskXp);
+
+        // The XP was already counted against the master when the parent was
+        // assigned, so only the delegate's own limits apply
+        let clock = Clock::get()?;
+        delegate.usage.record_assignment(clock.epoch, xp_reward, &config.sub_agent_limits)?;
+
+        let current_time = clock.unix_timestamp;
+
+        // Carve the child's reward out of the parent
+        parent_task.xp_reward -= xp_reward;
+        parent_task.delegated_xp += xp_reward;
+        parent_task.children_total += 1;
+        parent_task.updated_at = current_time;
+
+        task.assigned_to = delegate.key();
+        task.title = title;
+        task.description = description;
+        task.status = TaskStatus::Pending;
+        task.priority = priority;
+        task.xp_reward = xp_reward;
+        task.tags = tags;
+        task.created_at = current_time;
+        task.updated_at = current_time;
+        task.bump = ctx.bumps.task;
+        task.version = AgentTask::VERSION;
+        task.parent = Some(parent_task.key());
+        task.children_total = 0;
+        task.children_finished = 0;
+        task.delegated_xp = 0;
+        task.result_hash = [0; 32];
+        task.result_uri = String::new();
+
+        delegate.last_active_at = current_time;
+        master_agent.last_active_at = current_time;
+
+        let master_key = master_agent.key();
+        emit!(TaskDelegated {
+            header: master_agent.next_event_header(master_key, current_time),
+            task: task.key(),
+            parent_task: parent_task.key(),
+            from_sub_agent: ctx.accounts.sub_agent.key(),
+            to_sub_agent: delegate.key(),
+            xp_reward,
+        });
+
+        Ok(())
+    }
+
+    /// Completes a task, committing to its deliverable with the sha256
+    /// `result_hash` and a `result_uri` where it can be fetched.
+    pub fn complete_task(
+        ctx: Context<CompleteTask>,
+        result_hash: [u8; 32],
+        result_uri: String,
+    ) -> Result<()> {
+        ctx.accounts.config.require_active(PausableInstruction::CompleteTask)?;
+
+        let config = &ctx.accounts.config;
         let task = &mut ctx.accounts.task;
         let sub_agent = &mut ctx.accounts.sub_agent;
         let master_agent = &mut ctx.accounts.master_agent;
 
         require!(task.status == TaskStatus::Pending || task.status == TaskStatus::InProgress, 
                 AgentError::TaskAlreadyCompleted);
+        require!(task.children_finished == task.children_total, AgentError::ChildTasksPending);
+        require!(result_uri.len() <= MAX_RESULT_URI_LEN, AgentError::ResultUriTooLong);
+        finish_child_task(task, ctx.accounts.parent_task.as_mut(), 0)?;
 
-        let current_time = Clock::get()?.unix_timestamp;
+        // Enforce per-epoch completion limits
+        let clock = Clock::get()?;
+        sub_agent.usage.record_completion(clock.epoch, task.xp_reward, &config.sub_agent_limits)?;
+        master_agent.usage.record_completion(clock.epoch, task.xp_reward, &config.master_limits)?;
+
+        let current_time = clock.unix_timestamp;
         
         // Update task
         task.status = TaskStatus::Completed;
         task.completed_at = Some(current_time);
         task.updated_at = current_time;
+        task.result_hash = result_hash;
+        task.result_uri = result_uri;
 
         // Update sub-agent XP and metrics
         sub_agent.xp.current += task.xp_reward;
@@ -150,6 +285,23 @@ pub mod decentramind_agent {
         sub_agent.metrics.tasks_completed += 1;
         sub_agent.metrics.total_xp += task.xp_reward;
 
+        // Extend or restart the daily completion streak
+        let today = current_time / SECONDS_PER_DAY;
+        let last_day = sub_agent.reputation.last_completion_day;
+        if today == last_day + 1 {
+            sub_agent.metrics.active_streak_days += 1;
+        } else if today != last_day {
+            sub_agent.metrics.active_streak_days = 1;
+        }
+        sub_agent.reputation.last_completion_day = today;
+
+        let on_time = current_time - task.created_at <= task.priority.sla_seconds();
+        let streak_days = sub_agent.metrics.active_streak_days;
+        sub_agent.reputation.record_completion(on_time, current_time, streak_days);
+        sub_agent.metrics.success_rate = sub_agent.reputation.success_rate_bps() / 100;
+
+        let master_key = master_agent.key();
+
         // Check for level up
         if sub_agent.xp.current >= sub_agent.xp.next_level_requirement {
             sub_agent.xp.level += 1;
@@ -157,6 +309,7 @@ pub mod decentramind_agent {
             sub_agent.xp.next_level_requirement = calculate_next_level_requirement(sub_agent.xp.level);
             
             emit!(AgentLevelUp {
+                header: master_agent.next_event_header(master_key, current_time),
                 sub_agent: sub_agent.key(),
                 new_level: sub_agent.xp.level,
                 total_xp: sub_agent.xp.total,
@@ -173,15 +326,106 @@ pub mod decentramind_agent {
         master_agent.last_active_at = current_time;
 
         emit!(TaskCompleted {
+            header: master_agent.next_event_header(master_key, current_time),
             task: task.key(),
             sub_agent: sub_agent.key(),
             xp_earned: task.xp_reward,
             new_agent_level: sub_agent.xp.level,
+            result_hash: task.result_hash,
+            result_uri: task.result_uri.clone(),
+        });
+
+        emit!(ReputationUpdated {
+            header: master_agent.next_event_header(master_key, current_time),
+            sub_agent: sub_agent.key(),
+            score_bps: sub_agent.reputation.score_bps,
+        });
+
+        Ok(())
+    }
+
+    pub fn fail_task(ctx: Context<ReviewTask>) -> Result<()> {
+        ctx.accounts.config.require_active(PausableInstruction::FailTask)?;
+
+        let task = &mut ctx.accounts.task;
+        let sub_agent = &mut ctx.accounts.sub_agent;
+        let master_agent = &mut ctx.accounts.master_agent;
+
+        require!(task.status == TaskStatus::Pending || task.status == TaskStatus::InProgress,
+                AgentError::TaskAlreadyCompleted);
+        require!(task.children_finished == task.children_total, AgentError::ChildTasksPending);
+        // A failed child hands its carved-out XP back to the parent
+        finish_child_task(task, ctx.accounts.parent_task.as_mut(), task.xp_reward)?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+
+        task.status = TaskStatus::Failed;
+        task.updated_at = current_time;
+
+        let streak_days = sub_agent.metrics.active_streak_days;
+        sub_agent.reputation.record_failure(current_time, streak_days);
+        sub_agent.metrics.success_rate = sub_agent.reputation.success_rate_bps() / 100;
+        sub_agent.last_active_at = current_time;
+        master_agent.last_active_at = current_time;
+
+        let master_key = master_agent.key();
+        emit!(TaskFailed {
+            header: master_agent.next_event_header(master_key, current_time),
+            task: task.key(),
+            sub_agent: sub_agent.key(),
+        });
+        emit!(ReputationUpdated {
+            header: master_agent.next_event_header(master_key, current_time),
+            sub_agent: sub_agent.key(),
+            score_bps: sub_agent.reputation.score_bps,
         });
 
         Ok(())
     }
 
+    pub fn dispute_task(ctx: Context<ReviewTask>) -> Result<()> {
+        ctx.accounts.config.require_active(PausableInstruction::DisputeTask)?;
+
+        let task = &mut ctx.accounts.task;
+        let sub_agent = &mut ctx.accounts.sub_agent;
+        let master_agent = &mut ctx.accounts.master_agent;
+
+        require!(task.status == TaskStatus::Completed, AgentError::TaskNotCompleted);
+
+        let current_time = Clock::get()?.unix_timestamp;
+
+        task.status = TaskStatus::Disputed;
+        task.updated_at = current_time;
+
+        let streak_days = sub_agent.metrics.active_streak_days;
+        sub_agent.reputation.record_dispute(current_time, streak_days);
+        master_agent.last_active_at = current_time;
+
+        let master_key = master_agent.key();
+        emit!(TaskDisputed {
+            header: master_agent.next_event_header(master_key, current_time),
+            task: task.key(),
+            sub_agent: sub_agent.key(),
+        });
+        emit!(ReputationUpdated {
+            header: master_agent.next_event_header(master_key, current_time),
+            sub_agent: sub_agent.key(),
+            score_bps: sub_agent.reputation.score_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the sub-agent's reputation in basis points, decayed to the
+    /// current time. The instruction is read-only, so other programs can CPI
+    /// into it and read the score from the return data.
+    pub fn get_reputation(ctx: Context<GetReputation>) -> Result<u16> {
+        let sub_agent = &ctx.accounts.sub_agent;
+        let current_time = Clock::get()?.unix_timestamp;
+
+        Ok(sub_agent.reputation.score_at(current_time, sub_agent.metrics.active_streak_days))
+    }
+
     pub fn create_proposal(
         ctx: Context<CreateProposal>,
         title: String,
@@ -190,6 +434,8 @@ pub mod decentramind_agent {
         voting_period_days: u8,
         bump: u8,
     ) -> Result<()> {
+        ctx.accounts.config.require_active(PausableInstruction::CreateProposal)?;
+
         let proposal = &mut ctx.accounts.proposal;
         let master_agent = &mut ctx.accounts.master_agent;
 
@@ -207,12 +453,15 @@ pub mod decentramind_agent {
         proposal.created_at = current_time;
         proposal.voting_ends_at = voting_ends_at;
         proposal.bump = bump;
+        proposal.version = AgentProposal::VERSION;
 
         // Update master agent
         master_agent.dashboard.dao_proposals += 1;
         master_agent.last_active_at = current_time;
 
+        let master_key = master_agent.key();
         emit!(ProposalCreated {
+            header: master_agent.next_event_header(master_key, current_time),
             proposal: proposal.key(),
             master_agent: master_agent.key(),
             title: proposal.title.clone(),
@@ -226,11 +475,17 @@ pub mod decentramind_agent {
         ctx: Context<VoteOnProposal>,
         vote: bool, // true for yes, false for no
     ) -> Result<()> {
+        ctx.accounts.config.require_active(PausableInstruction::VoteOnProposal)?;
+
         let proposal = &mut ctx.accounts.proposal;
+        let master_agent = &mut ctx.accounts.master_agent;
         let voter = &ctx.accounts.voter;
 
+        let current_time = Clock::get()?.unix_timestamp;
+        let master_key = master_agent.key();
+
         require!(proposal.status == ProposalStatus::Pending, AgentError::ProposalNotActive);
-        require!(Clock::get()?.unix_timestamp <= proposal.voting_ends_at, AgentError::VotingPeriodEnded);
+        require!(current_time <= proposal.voting_ends_at, AgentError::VotingPeriodEnded);
 
         if vote {
             proposal.votes_for += 1;
@@ -243,6 +498,7 @@ pub mod decentramind_agent {
             proposal.status = ProposalStatus::Approved;
             
             emit!(ProposalApproved {
+                header: master_agent.next_event_header(master_key, current_time),
                 proposal: proposal.key(),
                 votes_for: proposal.votes_for,
                 votes_against: proposal.votes_against,
@@ -251,6 +507,7 @@ pub mod decentramind_agent {
             proposal.status = ProposalStatus::Rejected;
             
             emit!(ProposalRejected {
+                header: master_agent.next_event_header(master_key, current_time),
                 proposal: proposal.key(),
                 votes_for: proposal.votes_for,
                 votes_against: proposal.votes_against,
@@ -258,6 +515,7 @@ pub mod decentramind_agent {
         }
 
         emit!(VoteCast {
+            header: master_agent.next_event_header(master_key, current_time),
             proposal: proposal.key(),
             voter: voter.key(),
             vote,
@@ -267,6 +525,8 @@ pub mod decentramind_agent {
     }
 
     pub fn claim_reward(ctx: Context<ClaimReward>, amount: u64) -> Result<()> {
+        ctx.accounts.config.require_active(PausableInstruction::ClaimReward)?;
+
         let master_agent = &mut ctx.accounts.master_agent;
         
         // Transfer tokens from reward vault to user
@@ -285,8 +545,11 @@ pub mod decentramind_agent {
         master_agent.dashboard.rewards_claimed += amount;
         master_agent.last_active_at = Clock::get()?.unix_timestamp;
 
+        let current_time = Clock::get()?.unix_timestamp;
+        let master_key = master_agent.key();
         emit!(RewardClaimed {
-            master_agent: master_agent.key(),
+            header: master_agent.next_event_header(master_key, current_time),
+            master_agent: master_key,
             amount,
             recipient: ctx.accounts.user_token_account.key(),
         });
@@ -298,6 +561,8 @@ pub mod decentramind_agent {
         ctx: Context<UpdateAgentStatus>,
         new_status: AgentStatus,
     ) -> Result<()> {
+        ctx.accounts.config.require_active(PausableInstruction::UpdateAgentStatus)?;
+
         let sub_agent = &mut ctx.accounts.sub_agent;
         let master_agent = &mut ctx.accounts.master_agent;
 
@@ -320,7 +585,10 @@ pub mod decentramind_agent {
 
         master_agent.last_active_at = Clock::get()?.unix_timestamp;
 
+        let current_time = Clock::get()?.unix_timestamp;
+        let master_key = master_agent.key();
         emit!(AgentStatusUpdated {
+            header: master_agent.next_event_header(master_key, current_time),
             sub_agent: sub_agent.key(),
             old_status,
             new_status,
@@ -328,6 +596,134 @@ pub mod decentramind_agent {
 
         Ok(())
     }
+
+    /// Creates the program config. Only the program's upgrade authority can
+    /// call this, and it hands control to `admin` (e.g. a governance PDA).
+    pub fn initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = admin;
+        config.paused = false;
+        config.paused_instructions = 0;
+        config.bump = ctx.bumps.config;
+        config.version = ProgramConfig::VERSION;
+        config.sub_agent_limits = RateLimits::default();
+        config.master_limits = RateLimits::default();
+
+        emit!(ConfigInitialized { admin });
+
+        Ok(())
+    }
+
+    pub fn set_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let old_admin = config.admin;
+        config.admin = new_admin;
+
+        emit!(AdminUpdated {
+            old_admin,
+            new_admin,
+        });
+
+        Ok(())
+    }
+
+    /// Pauses or unpauses every mutating instruction at once.
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.paused = paused;
+
+        emit!(PauseUpdated {
+            admin: ctx.accounts.admin.key(),
+            paused: config.paused,
+            paused_instructions: config.paused_instructions,
+        });
+
+        Ok(())
+    }
+
+    /// Pauses or unpauses a single instruction.
+    pub fn set_instruction_paused(
+        ctx: Context<UpdateConfig>,
+        instruction: PausableInstruction,
+        paused: bool,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        if paused {
+            config.paused_instructions |= instruction.mask();
+        } else {
+            config.paused_instructions &= !instruction.mask();
+        }
+
+        emit!(PauseUpdated {
+            admin: ctx.accounts.admin.key(),
+            paused: config.paused,
+            paused_instructions: config.paused_instructions,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the per-epoch limits applied to each sub-agent and each master
+    /// agent. A zero limit is not enforced.
+    pub fn set_rate_limits(
+        ctx: Context<UpdateConfig>,
+        sub_agent_limits: RateLimits,
+        master_limits: RateLimits,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.sub_agent_limits = sub_agent_limits;
+        config.master_limits = master_limits;
+
+        emit!(RateLimitsUpdated {
+            admin: ctx.accounts.admin.key(),
+            sub_agent_limits,
+            master_limits,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrades an account written by an older program build to the current
+    /// layout in place, reallocating it if the new layout needs more space.
+    ///
+    /// Migration only adds fields with their default values, so it is
+    /// permissionless; `payer` funds any extra rent.
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        // The config itself may be on an old layout, so decode it leniently.
+        // Migrating the config is always allowed so a pause can't lock it.
+        if ctx.accounts.account.key() != ctx.accounts.config.key() {
+            decode_layout::<ProgramConfig>(&ctx.accounts.config)?
+                .require_active(PausableInstruction::MigrateAccount)?;
+        }
+
+        let account = ctx.accounts.account.to_account_info();
+        let payer = &ctx.accounts.payer;
+        let system_program = &ctx.accounts.system_program;
+
+        let discriminator: [u8; 8] = {
+            let data = account.try_borrow_data()?;
+            require!(data.len() >= 8, ErrorCode::AccountDiscriminatorNotFound);
+            data[..8].try_into().unwrap()
+        };
+
+        let (from_version, to_version) = if discriminator == MasterAgent::DISCRIMINATOR {
+            migrate_layout::<MasterAgent>(&account, payer, system_program)?
+        } else if discriminator == SubAgent::DISCRIMINATOR {
+            migrate_layout::<SubAgent>(&account, payer, system_program)?
+        } else if discriminator == AgentTask::DISCRIMINATOR {
+            migrate_layout::<AgentTask>(&account, payer, system_program)?
+        } else if discriminator == AgentProposal::DISCRIMINATOR {
+            migrate_layout::<AgentProposal>(&account, payer, system_program)?
+        } else if discriminator == ProgramConfig::DISCRIMINATOR {
+            migrate_layout::<ProgramConfig>(&account, payer, system_program)?
+        } else {
+            return err!(AgentError::UnsupportedAccountType);
+        };
+
+        msg!("Migrated {} from layout v{} to v{}", account.key, from_version, to_version);
+
+        Ok(())
+    }
 }
 
 // Helper function
@@ -335,6 +731,183 @@ fn calculate_next_level_requirement(level: u32) -> u64 {
     (level as u64 * 100) + 50 // Exponential growth
 }
 
+/// Marks a delegated task as finished on its parent, returning
+/// `returned_xp` of the carved-out reward. No-op for top-level tasks.
+fn finish_child_task(
+    task: &AgentTask,
+    parent_task: Option<&mut Account<Current<AgentTask>>>,
+    returned_xp: u64,
+) -> Result<()> {
+    let Some(parent_key) = task.parent else {
+        return Ok(());
+    };
+    let parent_task = parent_task.ok_or(AgentError::ParentTaskMismatch)?;
+    require_keys_eq!(parent_task.key(), parent_key, AgentError::ParentTaskMismatch);
+
+    parent_task.children_finished += 1;
+    parent_task.xp_reward += returned_xp;
+    parent_task.delegated_xp -= returned_xp;
+    parent_task.updated_at = Clock::get()?.unix_timestamp;
+
+    Ok(())
+}
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+pub const MAX_RESULT_URI_LEN: usize = 200;
+
+// Reputation tuning
+pub const REPUTATION_NEUTRAL_BPS: u16 = 50_00; // Score of an agent with no history
+const REPUTATION_UNIT: u64 = 1_000_000; // Weight of a single task event before decay
+const REPUTATION_HALF_LIFE: i64 = 30 * SECONDS_PER_DAY;
+const REPUTATION_MAX_STREAK_DAYS: u32 = 30;
+const SUCCESS_WEIGHT_BPS: u64 = 50_00;
+const ON_TIME_WEIGHT_BPS: u64 = 25_00;
+const DISPUTE_WEIGHT_BPS: u64 = 15_00;
+const STREAK_WEIGHT_BPS: u64 = 10_00;
+
+fn decay(value: u64, elapsed: i64) -> u64 {
+    decay::half_life_decay(value, elapsed, REPUTATION_HALF_LIFE)
+}
+
+fn ratio_bps(part: u64, whole: u64) -> u64 {
+    if whole == 0 {
+        return 0;
+    }
+    ((part.min(whole) as u128 * 10_000) / whole as u128) as u64
+}
+
+/// Decodes an account written with any layout version of `T`.
+fn decode_layout<T: Versioned + AnchorDeserialize>(account: &AccountInfo) -> Result<T> {
+    decode_layout_data(&account.try_borrow_data()?)
+}
+
+fn decode_layout_data<T: Versioned + AnchorDeserialize>(data: &[u8]) -> Result<T> {
+    require!(data.len() >= 8, ErrorCode::AccountDiscriminatorNotFound);
+    // Older layouts are a prefix of the current one and `init` zero-fills
+    // the unused tail, so zero-extending the data decodes an old account
+    // with every newer field (including `version` itself) at zero.
+    let mut buf = data[8..].to_vec();
+    buf.resize(buf.len() + T::LEN, 0);
+    T::deserialize(&mut buf.as_slice()).map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+}
+
+fn migrate_layout<'info, T>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<(u8, u8)>
+where
+    T: Versioned + AccountSerialize + AnchorDeserialize,
+{
+    let mut state = decode_layout::<T>(account)?;
+
+    let from_version = state.version();
+    require!(from_version <= T::VERSION, AgentError::UnsupportedAccountVersion);
+    require!(from_version < T::VERSION, AgentError::AccountAlreadyMigrated);
+
+    state.upgrade_from(from_version);
+    state.set_version(T::VERSION);
+
+    let mut serialized = Vec::with_capacity(8 + T::LEN);
+    state.try_serialize(&mut serialized)?;
+
+    let new_len = serialized.len().max(8 + T::LEN);
+    if new_len > account.data_len() {
+        let shortfall = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(account.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: account.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        account.realloc(new_len, true)?;
+    }
+
+    account.try_borrow_mut_data()?[..serialized.len()].copy_from_slice(&serialized);
+
+    Ok((from_version, T::VERSION))
+}
+
+/// Layout versioning for the program's accounts.
+///
+/// `version` follows the fields that predate versioning, and any field added
+/// later is appended after it. Bump `VERSION` whenever the layout grows and
+/// set non-zero defaults for the new fields in `upgrade_from`.
+pub trait Versioned {
+    /// Layout version written by this build of the program.
+    const VERSION: u8;
+    /// Maximum serialized size, excluding the discriminator.
+    const LEN: usize;
+
+    fn version(&self) -> u8;
+    fn set_version(&mut self, version: u8);
+
+    /// Fills in fields introduced after layout `from`.
+    fn upgrade_from(&mut self, _from: u8) {}
+}
+
+/// An account of type `T` that must already be on the current layout, as in
+/// `Account<'info, Current<MasterAgent>>`.
+///
+/// An old layout can be too short to decode as `T`, which would surface as
+/// a bare `AccountDidNotDeserialize`. The version is read leniently before
+/// the typed decode, so stale accounts fail with `UnsupportedAccountVersion`
+/// instead, pointing callers at `migrate_account`.
+#[derive(Clone)]
+pub struct Current<T>(T);
+
+impl<T> std::ops::Deref for Current<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> std::ops::DerefMut for Current<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Owner> Owner for Current<T> {
+    fn owner() -> Pubkey {
+        T::owner()
+    }
+}
+
+impl<T: AccountSerialize> AccountSerialize for Current<T> {
+    fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        self.0.try_serialize(writer)
+    }
+}
+
+impl<T> AccountDeserialize for Current<T>
+where
+    T: Versioned + Discriminator + AccountDeserialize + AnchorDeserialize,
+{
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        require!(buf.len() >= 8, ErrorCode::AccountDiscriminatorNotFound);
+        require!(buf[..8] == T::DISCRIMINATOR, ErrorCode::AccountDiscriminatorMismatch);
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        let version = decode_layout_data::<T>(buf)?.version();
+        require!(version == T::VERSION, AgentError::UnsupportedAccountVersion);
+        T::try_deserialize_unchecked(buf).map(Current)
+    }
+}
+
 // Account structures
 #[account]
 pub struct MasterAgent {
@@ -348,6 +921,24 @@ pub struct MasterAgent {
     pub created_at: i64,
     pub last_active_at: i64,
     pub bump: u8,
+    pub version: u8,
+    /// Sequence number of the next event emitted for this master.
+    pub event_seq: u64,
+    pub usage: EpochUsage,
+}
+
+impl MasterAgent {
+    /// Builds the header for the next event in this master's stream and
+    /// advances the sequence, so indexers can detect gaps.
+    pub fn next_event_header(&mut self, master_agent: Pubkey, timestamp: i64) -> EventHeader {
+        let header = EventHeader {
+            master_agent,
+            seq: self.event_seq,
+            timestamp,
+        };
+        self.event_seq += 1;
+        header
+    }
 }
 
 #[account]
@@ -362,6 +953,9 @@ pub struct SubAgent {
     pub created_at: i64,
     pub last_active_at: i64,
     pub bump: u8,
+    pub version: u8,
+    pub reputation: Reputation,
+    pub usage: EpochUsage,
 }
 
 #[account]
@@ -377,6 +971,13 @@ pub struct AgentTask {
     pub updated_at: i64,
     pub completed_at: Option<i64>,
     pub bump: u8,
+    pub version: u8,
+    pub parent: Option<Pubkey>, // set on tasks created by `delegate_task`
+    pub children_total: u32,
+    pub children_finished: u32, // completed or failed children
+    pub delegated_xp: u64, // XP currently carved out to children
+    pub result_hash: [u8; 32], // sha256 of the deliverable, set on completion
+    pub result_uri: String,
 }
 
 #[account]
@@ -393,9 +994,110 @@ pub struct AgentProposal {
     pub voting_ends_at: i64,
     pub implemented_at: Option<i64>,
     pub bump: u8,
+    pub version: u8,
+}
+
+impl Versioned for MasterAgent {
+    const VERSION: u8 = 3;
+    const LEN: usize = 32 + 64 + 64 + 4 + 8 + 33 + 44 + 8 + 8 + 1 + 1 + 8 + EpochUsage::LEN;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for SubAgent {
+    const VERSION: u8 = 3;
+    const LEN: usize = 32 + 1 + 64 + 128 + 1 + 32 + 64 + 8 + 8 + 1 + 1 + 50 + EpochUsage::LEN;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn upgrade_from(&mut self, from: u8) {
+        if from < 2 {
+            self.reputation.score_bps = REPUTATION_NEUTRAL_BPS;
+            self.reputation.updated_at = self.last_active_at;
+        }
+    }
+}
+
+impl Versioned for AgentTask {
+    const VERSION: u8 = 3;
+    const LEN: usize = 32 + 64 + 256 + 1 + 1 + 8 + 128 + 8 + 8 + 9 + 1 + 1 + 33 + 4 + 4 + 8
+        + 32 + 4 + MAX_RESULT_URI_LEN;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+impl Versioned for AgentProposal {
+    const VERSION: u8 = 1;
+    const LEN: usize = 32 + 64 + 256 + 1 + 1 + 4 + 4 + 4 + 8 + 8 + 9 + 1 + 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+}
+
+#[account]
+pub struct ProgramConfig {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub paused_instructions: u32, // bit set of `PausableInstruction` masks
+    pub bump: u8,
+    pub version: u8,
+    pub sub_agent_limits: RateLimits,
+    pub master_limits: RateLimits,
+}
+
+impl ProgramConfig {
+    pub fn require_active(&self, instruction: PausableInstruction) -> Result<()> {
+        require!(!self.paused, AgentError::ProgramPaused);
+        require!(self.paused_instructions & instruction.mask() == 0, AgentError::InstructionPaused);
+        Ok(())
+    }
+}
+
+impl Versioned for ProgramConfig {
+    const VERSION: u8 = 2;
+    const LEN: usize = 32 + 1 + 4 + 1 + 1 + RateLimits::LEN * 2;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
 }
 
 // Data structures
+/// Common prefix of every event, ordering it within its master agent's stream.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct EventHeader {
+    pub master_agent: Pubkey,
+    pub seq: u64,
+    pub timestamp: i64,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 pub struct DashboardKPIs {
     pub agent_xp_earned: u64,
@@ -424,6 +1126,171 @@ pub struct AgentMetrics {
     pub success_rate: u32, // percentage
 }
 
+/// Per-epoch limits on task assignment and completion. Zero disables a limit.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RateLimits {
+    pub max_tasks_per_epoch: u32,
+    pub max_xp_per_task: u64,
+    pub max_xp_per_epoch: u64,
+}
+
+impl RateLimits {
+    pub const LEN: usize = 4 + 8 + 8;
+
+    fn check(&self, tasks: u32, xp: u64, task_xp: u64) -> Result<()> {
+        require!(
+            self.max_xp_per_task == 0 || task_xp <= self.max_xp_per_task,
+            AgentError::XpPerTaskLimitExceeded
+        );
+        require!(
+            self.max_tasks_per_epoch == 0 || tasks <= self.max_tasks_per_epoch,
+            AgentError::TaskRateLimitExceeded
+        );
+        require!(
+            self.max_xp_per_epoch == 0 || xp <= self.max_xp_per_epoch,
+            AgentError::XpRateLimitExceeded
+        );
+        Ok(())
+    }
+}
+
+/// Task and XP counters for the current epoch, reset on the first use in a
+/// new epoch.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EpochUsage {
+    pub epoch: u64,
+    pub tasks_assigned: u32,
+    pub xp_assigned: u64,
+    pub tasks_completed: u32,
+    pub xp_completed: u64,
+}
+
+impl EpochUsage {
+    pub const LEN: usize = 8 + 4 + 8 + 4 + 8;
+
+    pub fn record_assignment(&mut self, epoch: u64, xp: u64, limits: &RateLimits) -> Result<()> {
+        self.roll_over(epoch);
+        let tasks = self.tasks_assigned.saturating_add(1);
+        let total_xp = self.xp_assigned.saturating_add(xp);
+        limits.check(tasks, total_xp, xp)?;
+
+        self.tasks_assigned = tasks;
+        self.xp_assigned = total_xp;
+        Ok(())
+    }
+
+    pub fn record_completion(&mut self, epoch: u64, xp: u64, limits: &RateLimits) -> Result<()> {
+        self.roll_over(epoch);
+        let tasks = self.tasks_completed.saturating_add(1);
+        let total_xp = self.xp_completed.saturating_add(xp);
+        limits.check(tasks, total_xp, xp)?;
+
+        self.tasks_completed = tasks;
+        self.xp_completed = total_xp;
+        Ok(())
+    }
+
+    fn roll_over(&mut self, epoch: u64) {
+        if self.epoch != epoch {
+            *self = EpochUsage {
+                epoch,
+                ..EpochUsage::default()
+            };
+        }
+    }
+}
+
+/// Time-decayed task history behind a sub-agent's reputation score.
+///
+/// Each task event adds `REPUTATION_UNIT` to its counters, and all counters
+/// halve every `REPUTATION_HALF_LIFE`, so old performance fades out.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Reputation {
+    pub score_bps: u16,
+    pub successes: u64,
+    pub failures: u64,
+    pub on_time: u64,
+    pub disputes: u64,
+    pub last_completion_day: i64, // unix day of the last completed task
+    pub updated_at: i64,
+}
+
+impl Reputation {
+    pub fn record_completion(&mut self, on_time: bool, now: i64, streak_days: u32) {
+        self.decay_to(now);
+        self.successes += REPUTATION_UNIT;
+        if on_time {
+            self.on_time += REPUTATION_UNIT;
+        }
+        self.score_bps = self.score(self.current_streak(streak_days, now));
+    }
+
+    pub fn record_failure(&mut self, now: i64, streak_days: u32) {
+        self.decay_to(now);
+        self.failures += REPUTATION_UNIT;
+        self.score_bps = self.score(self.current_streak(streak_days, now));
+    }
+
+    pub fn record_dispute(&mut self, now: i64, streak_days: u32) {
+        self.decay_to(now);
+        self.disputes += REPUTATION_UNIT;
+        self.score_bps = self.score(self.current_streak(streak_days, now));
+    }
+
+    /// Score as of `now`, without mutating the stored history.
+    pub fn score_at(&self, now: i64, streak_days: u32) -> u16 {
+        let mut decayed = *self;
+        decayed.decay_to(now);
+        decayed.score(self.current_streak(streak_days, now))
+    }
+
+    pub fn success_rate_bps(&self) -> u32 {
+        ratio_bps(self.successes, self.successes + self.failures) as u32
+    }
+
+    fn decay_to(&mut self, now: i64) {
+        let elapsed = now - self.updated_at;
+        self.successes = decay(self.successes, elapsed);
+        self.failures = decay(self.failures, elapsed);
+        self.on_time = decay(self.on_time, elapsed);
+        self.disputes = decay(self.disputes, elapsed);
+        self.updated_at = now;
+    }
+
+    // A streak only counts while the agent completed something today or yesterday
+    fn current_streak(&self, streak_days: u32, now: i64) -> u32 {
+        if now / SECONDS_PER_DAY - self.last_completion_day > 1 {
+            0
+        } else {
+            streak_days
+        }
+    }
+
+    fn score(&self, streak_days: u32) -> u16 {
+        if self.successes + self.failures == 0 {
+            return REPUTATION_NEUTRAL_BPS;
+        }
+
+        let success = ratio_bps(self.successes, self.successes + self.failures);
+        let on_time = ratio_bps(self.on_time, self.successes);
+        let undisputed = if self.successes == 0 {
+            0
+        } else {
+            10_000 - ratio_bps(self.disputes, self.successes)
+        };
+        let streak = ratio_bps(
+            streak_days.min(REPUTATION_MAX_STREAK_DAYS) as u64,
+            REPUTATION_MAX_STREAK_DAYS as u64,
+        );
+
+        let weighted = success * SUCCESS_WEIGHT_BPS
+            + on_time * ON_TIME_WEIGHT_BPS
+            + undisputed * DISPUTE_WEIGHT_BPS
+            + streak * STREAK_WEIGHT_BPS;
+        (weighted / 10_000) as u16
+    }
+}
+
 // Enums
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 pub enum SubAgentType {
@@ -451,6 +1318,7 @@ pub enum TaskStatus {
     InProgress,
     Completed,
     Failed,
+    Disputed,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
@@ -461,6 +1329,41 @@ pub enum TaskPriority {
     Critical,
 }
 
+impl TaskPriority {
+    /// Time allowed between assignment and completion for the task to count
+    /// as on time.
+    pub fn sla_seconds(&self) -> i64 {
+        match self {
+            TaskPriority::Low => 14 * SECONDS_PER_DAY,
+            TaskPriority::Medium => 7 * SECONDS_PER_DAY,
+            TaskPriority::High => 3 * SECONDS_PER_DAY,
+            TaskPriority::Critical => SECONDS_PER_DAY,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PausableInstruction {
+    InitializeMasterAgent,
+    CreateSubAgent,
+    AssignTask,
+    DelegateTask,
+    CompleteTask,
+    FailTask,
+    DisputeTask,
+    CreateProposal,
+    VoteOnProposal,
+    ClaimReward,
+    UpdateAgentStatus,
+    MigrateAccount,
+}
+
+impl PausableInstruction {
+    pub fn mask(self) -> u32 {
+        1 << self as u32
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 pub enum ProposalType {
     TaskAssignment,
@@ -484,11 +1387,16 @@ pub struct InitializeMasterAgent<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 64 + 64 + 4 + 8 + 33 + 8 + 8 + 8 + 1, // Adjust size as needed
+        space = 8 + MasterAgent::LEN,
         seeds = [b"master_agent", authority.key().as_ref()],
         bump
     )]
     pub master_agent: Account<'info, MasterAgent>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Current<ProgramConfig>>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -500,7 +1408,7 @@ pub struct CreateSubAgent<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 1 + 64 + 128 + 1 + 32 + 64 + 8 + 8 + 1, // Adjust size as needed
+        space = 8 + SubAgent::LEN,
         seeds = [b"sub_agent", master_agent.key().as_ref(), &[agent_type as u8]],
         bump
     )]
@@ -510,7 +1418,12 @@ pub struct CreateSubAgent<'info> {
         seeds = [b"master_agent", authority.key().as_ref()],
         bump = master_agent.bump
     )]
-    pub master_agent: Account<'info, MasterAgent>,
+    pub master_agent: Account<'info, Current<MasterAgent>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Current<ProgramConfig>>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -522,19 +1435,24 @@ pub struct AssignTask<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 64 + 256 + 1 + 1 + 8 + 128 + 8 + 8 + 9 + 1, // Adjust size as needed
+        space = 8 + AgentTask::LEN,
         seeds = [b"task", sub_agent.key().as_ref(), &Clock::get().unwrap().unix_timestamp.to_le_bytes()],
         bump
     )]
     pub task: Account<'info, AgentTask>,
     #[account(mut)]
-    pub sub_agent: Account<'info, SubAgent>,
+    pub sub_agent: Account<'info, Current<SubAgent>>,
     #[account(
         mut,
         seeds = [b"master_agent", authority.key().as_ref()],
         bump = master_agent.bump
     )]
-    pub master_agent: Account<'info, MasterAgent>,
+    pub master_agent: Account<'info, Current<MasterAgent>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Current<ProgramConfig>>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -542,13 +1460,107 @@ pub struct AssignTask<'info> {
 
 #[derive(Accounts)]
 pub struct CompleteTask<'info> {
+    #[account(
+        mut,
+        constraint = task.assigned_to == sub_agent.key() @ AgentError::TaskNotFound
+    )]
+    pub task: Account<'info, Current<AgentTask>>,
+    #[account(
+        mut,
+        has_one = master_agent @ AgentError::InsufficientPrivileges
+    )]
+    pub sub_agent: Account<'info, Current<SubAgent>>,
+    #[account(
+        mut,
+        seeds = [b"master_agent", authority.key().as_ref()],
+        bump = master_agent.bump
+    )]
+    pub master_agent: Account<'info, Current<MasterAgent>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Current<ProgramConfig>>,
+    pub authority: Signer<'info>,
+    /// Required when `task` was created by `delegate_task`.
     #[account(mut)]
+    pub parent_task: Option<Account<'info, Current<AgentTask>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(title: String, description: String, priority: TaskPriority, xp_reward: u64, tags: Vec<String>)]
+pub struct DelegateTask<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AgentTask::LEN,
+        seeds = [b"subtask", parent_task.key().as_ref(), &parent_task.children_total.to_le_bytes()],
+        bump
+    )]
     pub task: Account<'info, AgentTask>,
+    #[account(
+        mut,
+        constraint = parent_task.assigned_to == sub_agent.key() @ AgentError::TaskNotFound
+    )]
+    pub parent_task: Account<'info, Current<AgentTask>>,
+    #[account(
+        has_one = master_agent @ AgentError::InsufficientPrivileges
+    )]
+    pub sub_agent: Account<'info, Current<SubAgent>>,
+    #[account(
+        mut,
+        has_one = master_agent @ AgentError::InsufficientPrivileges,
+        constraint = delegate.key() != sub_agent.key() @ AgentError::InvalidDelegate
+    )]
+    pub delegate: Account<'info, Current<SubAgent>>,
+    #[account(
+        mut,
+        seeds = [b"master_agent", authority.key().as_ref()],
+        bump = master_agent.bump
+    )]
+    pub master_agent: Account<'info, Current<MasterAgent>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Current<ProgramConfig>>,
     #[account(mut)]
-    pub sub_agent: Account<'info, SubAgent>,
-    #[account(mut)]
-    pub master_agent: Account<'info, MasterAgent>,
     pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReviewTask<'info> {
+    #[account(
+        mut,
+        constraint = task.assigned_to == sub_agent.key() @ AgentError::TaskNotFound
+    )]
+    pub task: Account<'info, Current<AgentTask>>,
+    #[account(
+        mut,
+        has_one = master_agent @ AgentError::InsufficientPrivileges
+    )]
+    pub sub_agent: Account<'info, Current<SubAgent>>,
+    #[account(
+        mut,
+        seeds = [b"master_agent", authority.key().as_ref()],
+        bump = master_agent.bump
+    )]
+    pub master_agent: Account<'info, Current<MasterAgent>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Current<ProgramConfig>>,
+    pub authority: Signer<'info>,
+    /// Required when `task` was created by `delegate_task`.
+    #[account(mut)]
+    pub parent_task: Option<Account<'info, Current<AgentTask>>>,
+}
+
+#[derive(Accounts)]
+pub struct GetReputation<'info> {
+    pub sub_agent: Account<'info, Current<SubAgent>>,
 }
 
 #[derive(Accounts)]
@@ -557,7 +1569,7 @@ pub struct CreateProposal<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 64 + 256 + 1 + 1 + 4 + 4 + 4 + 8 + 8 + 9 + 1, // Adjust size as needed
+        space = 8 + AgentProposal::LEN,
         seeds = [b"proposal", master_agent.key().as_ref(), &Clock::get().unwrap().unix_timestamp.to_le_bytes()],
         bump
     )]
@@ -567,7 +1579,12 @@ pub struct CreateProposal<'info> {
         seeds = [b"master_agent", authority.key().as_ref()],
         bump = master_agent.bump
     )]
-    pub master_agent: Account<'info, MasterAgent>,
+    pub master_agent: Account<'info, Current<MasterAgent>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Current<ProgramConfig>>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -576,14 +1593,28 @@ pub struct CreateProposal<'info> {
 #[derive(Accounts)]
 pub struct VoteOnProposal<'info> {
     #[account(mut)]
-    pub proposal: Account<'info, AgentProposal>,
+    pub proposal: Account<'info, Current<AgentProposal>>,
+    #[account(
+        mut,
+        address = proposal.master_agent
+    )]
+    pub master_agent: Account<'info, Current<MasterAgent>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Current<ProgramConfig>>,
     pub voter: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct ClaimReward<'info> {
-    #[account(mut)]
-    pub master_agent: Account<'info, MasterAgent>,
+    #[account(
+        mut,
+        seeds = [b"master_agent", authority.key().as_ref()],
+        bump = master_agent.bump
+    )]
+    pub master_agent: Account<'info, Current<MasterAgent>>,
     #[account(mut)]
     pub reward_vault: Account<'info, TokenAccount>,
     #[account(mut)]
@@ -591,21 +1622,85 @@ pub struct ClaimReward<'info> {
     /// CHECK: This is the PDA authority for the reward vault
     pub reward_vault_authority: AccountInfo<'info>,
     pub token_program: Program<'info, Token>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Current<ProgramConfig>>,
     pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct UpdateAgentStatus<'info> {
+    #[account(
+        mut,
+        has_one = master_agent @ AgentError::InsufficientPrivileges
+    )]
+    pub sub_agent: Account<'info, Current<SubAgent>>,
+    #[account(
+        mut,
+        seeds = [b"master_agent", authority.key().as_ref()],
+        bump = master_agent.bump
+    )]
+    pub master_agent: Account<'info, Current<MasterAgent>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Current<ProgramConfig>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProgramConfig::LEN,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ AgentError::InsufficientPrivileges)]
+    pub program: Program<'info, crate::program::DecentramindAgent>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ AgentError::InsufficientPrivileges)]
+    pub program_data: Account<'info, ProgramData>,
     #[account(mut)]
-    pub sub_agent: Account<'info, SubAgent>,
-    #[account(mut)]
-    pub master_agent: Account<'info, MasterAgent>,
     pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ AgentError::InsufficientPrivileges
+    )]
+    pub config: Account<'info, Current<ProgramConfig>>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: Any account owned by this program; the layout is identified by
+    /// its discriminator and decoded in `migrate_account`.
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+    /// CHECK: The program config, decoded in `migrate_account` because it
+    /// may itself be the account being migrated.
+    #[account(seeds = [b"config"], bump, owner = crate::ID)]
+    pub config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 // Events
 #[event]
 pub struct MasterAgentInitialized {
+    pub header: EventHeader,
     pub master_agent: Pubkey,
     pub authority: Pubkey,
     pub name: String,
@@ -613,6 +1708,7 @@ pub struct MasterAgentInitialized {
 
 #[event]
 pub struct SubAgentCreated {
+    pub header: EventHeader,
     pub sub_agent: Pubkey,
     pub master_agent: Pubkey,
     pub agent_type: SubAgentType,
@@ -621,22 +1717,58 @@ pub struct SubAgentCreated {
 
 #[event]
 pub struct TaskAssigned {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub sub_agent: Pubkey,
     pub title: String,
     pub xp_reward: u64,
 }
 
+#[event]
+pub struct TaskDelegated {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub parent_task: Pubkey,
+    pub from_sub_agent: Pubkey,
+    pub to_sub_agent: Pubkey,
+    pub xp_reward: u64,
+}
+
 #[event]
 pub struct TaskCompleted {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub sub_agent: Pubkey,
     pub xp_earned: u64,
     pub new_agent_level: u32,
+    pub result_hash: [u8; 32],
+    pub result_uri: String,
+}
+
+#[event]
+pub struct TaskFailed {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub sub_agent: Pubkey,
+}
+
+#[event]
+pub struct TaskDisputed {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub sub_agent: Pubkey,
+}
+
+#[event]
+pub struct ReputationUpdated {
+    pub header: EventHeader,
+    pub sub_agent: Pubkey,
+    pub score_bps: u16,
 }
 
 #[event]
 pub struct AgentLevelUp {
+    pub header: EventHeader,
     pub sub_agent: Pubkey,
     pub new_level: u32,
     pub total_xp: u64,
@@ -644,6 +1776,7 @@ pub struct AgentLevelUp {
 
 #[event]
 pub struct ProposalCreated {
+    pub header: EventHeader,
     pub proposal: Pubkey,
     pub master_agent: Pubkey,
     pub title: String,
@@ -652,6 +1785,7 @@ pub struct ProposalCreated {
 
 #[event]
 pub struct ProposalApproved {
+    pub header: EventHeader,
     pub proposal: Pubkey,
     pub votes_for: u32,
     pub votes_against: u32,
@@ -659,6 +1793,7 @@ pub struct ProposalApproved {
 
 #[event]
 pub struct ProposalRejected {
+    pub header: EventHeader,
     pub proposal: Pubkey,
     pub votes_for: u32,
     pub votes_against: u32,
@@ -666,6 +1801,7 @@ pub struct ProposalRejected {
 
 #[event]
 pub struct VoteCast {
+    pub header: EventHeader,
     pub proposal: Pubkey,
     pub voter: Pubkey,
     pub vote: bool,
@@ -673,6 +1809,7 @@ pub struct VoteCast {
 
 #[event]
 pub struct RewardClaimed {
+    pub header: EventHeader,
     pub master_agent: Pubkey,
     pub amount: u64,
     pub recipient: Pubkey,
@@ -680,11 +1817,38 @@ pub struct RewardClaimed {
 
 #[event]
 pub struct AgentStatusUpdated {
+    pub header: EventHeader,
     pub sub_agent: Pubkey,
     pub old_status: AgentStatus,
     pub new_status: AgentStatus,
 }
 
+// Config events are program-wide, so they carry no per-master header
+#[event]
+pub struct ConfigInitialized {
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct AdminUpdated {
+    pub old_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+#[event]
+pub struct RateLimitsUpdated {
+    pub admin: Pubkey,
+    pub sub_agent_limits: RateLimits,
+    pub master_limits: RateLimits,
+}
+
+#[event]
+pub struct PauseUpdated {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub paused_instructions: u32,
+}
+
 // Errors
 #[error_code]
 pub enum AgentError {
@@ -704,4 +1868,32 @@ pub enum AgentError {
     TaskNotFound,
     #[msg("Proposal not found")]
     ProposalNotFound,
+    #[msg("Task has not been completed")]
+    TaskNotCompleted,
+    #[msg("Task has unfinished child tasks")]
+    ChildTasksPending,
+    #[msg("Parent task account is missing or does not match")]
+    ParentTaskMismatch,
+    #[msg("Task does not have enough XP reward left to delegate")]
+    InsufficientTaskXp,
+    #[msg("Tasks can only be delegated to a different sub-agent")]
+    InvalidDelegate,
+    #[msg("Result URI is too long")]
+    ResultUriTooLong,
+    #[msg("Program is paused")]
+    ProgramPaused,
+    #[msg("Instruction is paused")]
+    InstructionPaused,
+    #[msg("Too many tasks for this agent in the current epoch")]
+    TaskRateLimitExceeded,
+    #[msg("Task XP reward exceeds the per-task limit")]
+    XpPerTaskLimitExceeded,
+    #[msg("Too much XP for this agent in the current epoch")]
+    XpRateLimitExceeded,
+    #[msg("Unsupported account layout version; run migrate_account or upgrade the client")]
+    UnsupportedAccountVersion,
+    #[msg("Account already uses the current layout")]
+    AccountAlreadyMigrated,
+    #[msg("Account type cannot be migrated")]
+    UnsupportedAccountType,
 } 
\ No newline at end of file